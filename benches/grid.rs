@@ -0,0 +1,167 @@
+// compare l'ancien stockage du terrain, une HashMap indexée par position, à la Grid dense
+// qui l'a remplacé, pour les trois accès les plus fréquents : la réponse à MAP (repr),
+// la lettre d'une case (letter_at) et le parcours des cases visibles au dessin
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use oorandom::Rand32;
+use std::collections::HashMap;
+
+#[allow(dead_code)]
+#[path = "../src/game"]
+mod game {
+    pub mod grid;
+    pub mod pos;
+}
+
+use game::grid::Grid;
+use game::pos::Pos;
+
+const SIZE: u32 = 75;
+const DIAMONDS: u32 = 10;
+// cases visibles autour du joueur, comme dans Game::draw
+const VIEW: (u32, u32) = (31, 17);
+
+// lettres des cases de la réponse à MAP, dans l'ordre de map::Tile
+const LETTERS: [char; 5] = [' ', 'M', 'P', 'B', 'S'];
+
+// même terrain sous les deux formes, avec les diamants et le joueur par dessus
+struct Terrains {
+    hash_map: HashMap<Pos, u8>,
+    grid: Grid<u8>,
+    diamonds: Vec<Pos>,
+    player: Pos,
+}
+
+impl Terrains {
+    fn new(seed: u64) -> Self {
+        let mut rng = Rand32::new(seed);
+        let mut hash_map = HashMap::new();
+        let mut grid = Grid::new(SIZE, SIZE, 0);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let tile = rng.rand_range(0..LETTERS.len() as u32) as u8;
+                hash_map.insert(Pos::new(x, y), tile);
+                grid[Pos::new(x, y)] = tile;
+            }
+        }
+        let diamonds = (0..DIAMONDS)
+            .map(|_| Pos::new(rng.rand_range(0..SIZE), rng.rand_range(0..SIZE)))
+            .collect();
+        Self {
+            hash_map,
+            grid,
+            diamonds,
+            player: Pos::new(SIZE / 3, SIZE / 3),
+        }
+    }
+
+    fn overlay(&self, position: Pos) -> Option<char> {
+        if self.player == position {
+            Some('J')
+        } else if self.diamonds.contains(&position) {
+            Some('D')
+        } else {
+            None
+        }
+    }
+
+    fn hash_map_letter_at(&self, position: Pos) -> char {
+        self.overlay(position)
+            .unwrap_or_else(|| LETTERS[*self.hash_map.get(&position).unwrap() as usize])
+    }
+
+    fn grid_letter_at(&self, position: Pos) -> char {
+        self.overlay(position)
+            .unwrap_or_else(|| LETTERS[self.grid[position] as usize])
+    }
+
+    // ancienne version de Map::repr : une recherche par case
+    fn hash_map_repr(&self) -> String {
+        let mut string = String::with_capacity((SIZE * SIZE) as usize);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                string.push(self.hash_map_letter_at(Pos::new(x, y)));
+            }
+        }
+        string
+    }
+
+    // version actuelle de Map::repr : parcours des lignes, puis diamants et joueur
+    fn grid_repr(&self) -> String {
+        let mut letters: Vec<char> = self
+            .grid
+            .rows()
+            .flatten()
+            .map(|tile| LETTERS[*tile as usize])
+            .collect();
+        for d in &self.diamonds {
+            letters[(d.y * SIZE + d.x) as usize] = 'D';
+        }
+        letters[(self.player.y * SIZE + self.player.x) as usize] = 'J';
+        letters.into_iter().collect()
+    }
+
+    // cases visibles, lues une à une comme au dessin sans cache du terrain
+    fn visible(&self) -> impl Iterator<Item = Pos> {
+        let left = self.player.x.saturating_sub(VIEW.0 / 2);
+        let top = self.player.y.saturating_sub(VIEW.1 / 2);
+        (top..top + VIEW.1).flat_map(move |y| (left..left + VIEW.0).map(move |x| Pos::new(x, y)))
+    }
+}
+
+fn repr(c: &mut Criterion) {
+    let terrains = Terrains::new(0);
+    assert_eq!(terrains.hash_map_repr(), terrains.grid_repr());
+
+    let mut group = c.benchmark_group("repr");
+    group.bench_function("hash_map", |b| b.iter(|| terrains.hash_map_repr()));
+    group.bench_function("grid", |b| b.iter(|| terrains.grid_repr()));
+    group.finish();
+}
+
+fn letter_at(c: &mut Criterion) {
+    let terrains = Terrains::new(0);
+    let positions: Vec<Pos> = (0..SIZE * SIZE)
+        .map(|i| Pos::new(i % SIZE, i / SIZE))
+        .collect();
+
+    let mut group = c.benchmark_group("letter_at");
+    group.bench_function("hash_map", |b| {
+        b.iter(|| {
+            for position in &positions {
+                black_box(terrains.hash_map_letter_at(*position));
+            }
+        })
+    });
+    group.bench_function("grid", |b| {
+        b.iter(|| {
+            for position in &positions {
+                black_box(terrains.grid_letter_at(*position));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn draw(c: &mut Criterion) {
+    let terrains = Terrains::new(0);
+
+    let mut group = c.benchmark_group("draw");
+    group.bench_function("hash_map", |b| {
+        b.iter(|| {
+            for position in terrains.visible() {
+                black_box(terrains.hash_map.get(&position).copied());
+            }
+        })
+    });
+    group.bench_function("grid", |b| {
+        b.iter(|| {
+            for position in terrains.visible() {
+                black_box(terrains.grid.get(position));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, repr, letter_at, draw);
+criterion_main!(benches);
@@ -1,26 +1,43 @@
 use image::io::Reader as ImageReader;
+use image::ColorType;
 use proc_macro::TokenStream;
 
+// (largeur, hauteur, octets) d'une image RGBA 8 bits, décodée à la compilation
 #[proc_macro]
 pub fn include_raw_image(item: TokenStream) -> TokenStream {
     let mut item_str = item.to_string();
     item_str.pop();
     item_str.remove(0);
 
-    let img = ImageReader::open(item_str)
+    let img = ImageReader::open(&item_str)
         .unwrap()
         .with_guessed_format()
         .unwrap()
         .decode()
         .unwrap();
 
-    let mut array = String::from("[");
+    // les octets sont copiés tels quels dans une texture de quatre octets par pixel
+    if img.color() != ColorType::Rgba8 {
+        return format!(
+            "compile_error!(\"{} : image {:?}, seules les images Rgba8 sont acceptées\")",
+            item_str,
+            img.color()
+        )
+        .parse()
+        .unwrap();
+    }
+
+    let mut array = format!("({}u32, {}u32, [", img.width(), img.height());
 
-    for byte in img.as_bytes() {
-        array.push_str(&format!("{},", byte));
+    for (i, byte) in img.as_bytes().iter().enumerate() {
+        if i == 0 {
+            array.push_str(&format!("{}u8,", byte));
+        } else {
+            array.push_str(&format!("{},", byte));
+        }
     }
 
-    array.push(']');
+    array.push_str("])");
 
     array.parse().unwrap()
 }
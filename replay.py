@@ -0,0 +1,87 @@
+# rejoue les messages reçus par le serveur lors d'une connexion enregistrée avec
+# --transcript <dossier>, en respectant l'écart en ticks entre les messages
+#
+# pour retrouver la même partie, lancer le serveur avec les paramètres affichés au début
+# (graine, taille de carte, BPM...), lus dans l'en-tête du transcript
+#
+# utilisation : python3 replay.py <transcript> [--vitesse <facteur>] [--hote <hôte:port>]
+
+import re
+import socket
+import struct
+import sys
+import threading
+import time
+
+DEFAULT_TICK_MS = 125  # durée d'un tick à 120 BPM, pour les transcripts sans en-tête
+
+
+def unescape(data):
+    return re.sub(
+        rb"\\(\\|x([0-9a-f]{2}))",
+        lambda m: bytes([int(m.group(2), 16)]) if m.group(2) else b"\\",
+        data.encode("utf-8"),
+    )
+
+
+def read(path):
+    header = {}
+    messages = []
+    with open(path, encoding="utf-8") as transcript:
+        for line in transcript:
+            _, tick, direction, data = line.rstrip("\n").split(" ", 3)
+            if direction == "=":
+                header = dict(entry.split("=", 1) for entry in data.split())
+            elif direction == "<":
+                messages.append((int(tick), unescape(data)))
+    return header, messages
+
+
+def show(conn):
+    while True:
+        data = conn.recv(4096)
+        if not data:
+            print("# connexion fermée par le serveur")
+            return
+        sys.stdout.write(data.decode("utf-8", "backslashreplace"))
+        sys.stdout.flush()
+
+
+def main(args):
+    path = args[0]
+    options = dict(zip(args[1::2], args[2::2]))
+    speed = float(options.get("--vitesse", "1"))
+    host, port = options.get("--hote", "127.0.0.1:54321").rsplit(":", 1)
+
+    header, messages = read(path)
+    if header:
+        print("# partie : " + " ".join("{}={}".format(*entry) for entry in header.items()))
+    tick = int(header.get("tick_ms", DEFAULT_TICK_MS)) / 1000
+
+    with socket.create_connection((host, int(port))) as conn:
+        threading.Thread(target=show, args=(conn,), daemon=True).start()
+
+        binary = False
+        previous = None
+        for at, message in messages:
+            if previous is not None:
+                time.sleep(max(at - previous, 0) * tick / speed)
+            previous = at
+
+            if binary:
+                conn.sendall(struct.pack(">I", len(message)) + message)
+            else:
+                conn.sendall(message + b"\n")
+            # les messages suivants changent de format de trames ; comme le serveur, on découpe
+            # sur les espaces sans ignorer la casse
+            words = message.split()
+            if words == [b"OPTION", b"FRAMING", b"BINARY"]:
+                binary = True
+            elif words == [b"OPTION", b"FRAMING", b"TEXT"]:
+                binary = False
+
+        time.sleep(1)
+
+
+if __name__ == "__main__":
+    main(sys.argv[1:])
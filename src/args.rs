@@ -0,0 +1,82 @@
+// options de la ligne de commande, chacune suivie de sa valeur
+const OPTIONS: [&str; 11] = [
+    "--config",
+    "--lang",
+    "--log-level",
+    "--log-format",
+    "--transcript",
+    "--idle-timeout",
+    "--game-timeout",
+    "--commands-per-beat",
+    "--max-refused",
+    "--terrain-cache",
+    "--assets",
+];
+
+// valeur de l'option key, c'est-à-dire l'argument qui la suit ; None si l'option est absente
+// ou suivie d'une autre option. Si elle est donnée plusieurs fois, la dernière l'emporte.
+pub fn option<'a>(args: &'a [String], key: &str) -> Option<&'a str> {
+    let index = args.iter().rposition(|arg| arg == key)?;
+    args.get(index + 1)
+        .map(|value| value.as_str())
+        .filter(|value| !OPTIONS.contains(value))
+}
+
+// signale les arguments qui seront ignorés : options inconnues, options sans valeur et
+// valeurs en trop
+pub fn check(args: &[String]) {
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let has_value = args
+            .get(i + 1)
+            .is_some_and(|value| !OPTIONS.contains(&value.as_str()));
+        if !OPTIONS.contains(&arg) {
+            warn!("argument « {} » inconnu ignoré", arg);
+            i += 1;
+        } else if has_value {
+            i += 2;
+        } else {
+            warn!("option « {} » sans valeur ignorée", arg);
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn option_reads_the_next_argument() {
+        let args = args("--lang en --log-level debug");
+        assert_eq!(option(&args, "--lang"), Some("en"));
+        assert_eq!(option(&args, "--log-level"), Some("debug"));
+        assert_eq!(option(&args, "--config"), None);
+    }
+
+    #[test]
+    fn missing_value_does_not_shift_other_options() {
+        let args = args("--lang --log-level debug --assets");
+        assert_eq!(option(&args, "--lang"), None);
+        assert_eq!(option(&args, "--log-level"), Some("debug"));
+        assert_eq!(option(&args, "--assets"), None);
+    }
+
+    #[test]
+    fn extra_argument_does_not_shift_other_options() {
+        let args = args("inconnu --transcript journaux --idle-timeout 30");
+        assert_eq!(option(&args, "--transcript"), Some("journaux"));
+        assert_eq!(option(&args, "--idle-timeout"), Some("30"));
+    }
+
+    #[test]
+    fn last_occurrence_wins() {
+        let args = args("--lang fr --lang en");
+        assert_eq!(option(&args, "--lang"), Some("en"));
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt;
+use std::sync::mpsc::{RecvError, SendError};
+
+// erreurs qui mettent fin à une connexion, sans arrêter le serveur
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    ChannelClosed,
+    // le client ne lit plus ce qui lui est envoyé
+    WriteTimeout,
+    ThreadPanicked,
+    ThreadNotStarted(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "erreur d'entrée/sortie : {}", err),
+            Error::ChannelClosed => write!(f, "le canal entre le jeu et le réseau est fermé"),
+            Error::WriteTimeout => write!(f, "le client ne lit plus les messages envoyés"),
+            Error::ThreadPanicked => write!(f, "le thread réseau a paniqué"),
+            Error::ThreadNotStarted(err) => {
+                write!(f, "impossible de démarrer le thread réseau : {}", err)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl<T> From<SendError<T>> for Error {
+    fn from(_: SendError<T>) -> Self {
+        Error::ChannelClosed
+    }
+}
+
+impl From<RecvError> for Error {
+    fn from(_: RecvError) -> Self {
+        Error::ChannelClosed
+    }
+}
@@ -0,0 +1,384 @@
+use crate::i18n::Language;
+use crate::interface::{Framing, ProtocolOption, Replies, ServerMessage};
+use std::io::{ErrorKind, Read, Write};
+
+// taille maximale d'une ligne ou d'une trame envoyée par le client
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+#[derive(Debug, PartialEq)]
+pub enum Incoming {
+    Message,
+    Oversized,
+    Closed,
+}
+
+// découpe les données reçues en messages ; ce qui a été reçu au-delà d'un message
+// est gardé pour l'appel suivant, y compris quand le client change de format de trames
+pub struct MessageReader {
+    pending: Vec<u8>,
+    discarding: bool,
+}
+
+impl MessageReader {
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            discarding: false,
+        }
+    }
+
+    pub fn read<R: Read>(
+        &mut self,
+        stream: &mut R,
+        framing: Framing,
+        output: &mut Vec<u8>,
+    ) -> Result<Incoming, std::io::Error> {
+        let mut buffer = [0 as u8; 512];
+        output.clear();
+
+        loop {
+            let incoming = match framing {
+                Framing::Text => self.next_line(output),
+                Framing::Binary => self.next_frame(output)?,
+            };
+            if let Some(incoming) = incoming {
+                return Ok(incoming);
+            }
+
+            let size = stream.read(&mut buffer)?;
+            if size == 0 {
+                return Ok(Incoming::Closed);
+            }
+            self.pending.extend_from_slice(&buffer[0..size]);
+        }
+    }
+
+    // une ligne trop longue est signalée une seule fois, puis ignorée jusqu'à sa fin
+    fn next_line(&mut self, output: &mut Vec<u8>) -> Option<Incoming> {
+        loop {
+            match self.pending.iter().position(|c| *c == 10) {
+                Some(end) => {
+                    let mut line: Vec<u8> = self.pending.drain(0..end + 1).collect();
+                    if self.discarding {
+                        self.discarding = false;
+                        continue;
+                    }
+
+                    line.pop();
+                    if line.last() == Some(&13) {
+                        line.pop();
+                    }
+                    if line.len() > MAX_MESSAGE_LENGTH {
+                        return Some(Incoming::Oversized);
+                    }
+                    output.extend(line);
+                    return Some(Incoming::Message);
+                }
+                None => {
+                    if self.discarding {
+                        self.pending.clear();
+                    } else if self.pending.len() > MAX_MESSAGE_LENGTH {
+                        self.pending.clear();
+                        self.discarding = true;
+                        return Some(Incoming::Oversized);
+                    }
+                    return None;
+                }
+            }
+        }
+    }
+
+    // une trame est composée de sa taille (sur 4 octets, gros-boutiste) suivie de son contenu ;
+    // on ne peut pas se resynchroniser après une taille invalide, donc la connexion est fermée
+    fn next_frame(&mut self, output: &mut Vec<u8>) -> Result<Option<Incoming>, std::io::Error> {
+        if self.pending.len() < 4 {
+            return Ok(None);
+        }
+
+        let length = u32::from_be_bytes([
+            self.pending[0],
+            self.pending[1],
+            self.pending[2],
+            self.pending[3],
+        ]) as usize;
+        if length > MAX_MESSAGE_LENGTH {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "trame trop longue",
+            ));
+        }
+
+        if self.pending.len() >= 4 + length {
+            output.extend(self.pending.drain(0..4 + length).skip(4));
+            Ok(Some(Incoming::Message))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+// format des messages envoyés à un client, tel qu'il l'a choisi avec OPTION
+#[derive(Copy, Clone)]
+pub struct Encoding {
+    pub framing: Framing,
+    pub language: Option<Language>,
+    pub replies: Replies,
+}
+
+impl Encoding {
+    pub fn new() -> Self {
+        Self {
+            framing: Framing::Text,
+            language: Some(Language::French),
+            replies: Replies::Plain,
+        }
+    }
+}
+
+// la réponse à OPTION FRAMING ou OPTION LANG est encore envoyée dans l'ancien format
+pub fn write_message<W: Write>(
+    stream: &mut W,
+    msg: ServerMessage,
+    encoding: &mut Encoding,
+) -> Result<usize, std::io::Error> {
+    let switch = match msg {
+        ServerMessage::OptionSet(option) => Some(option),
+        _ => None,
+    };
+
+    let size = match encoding.framing {
+        Framing::Text => write_line(stream, &msg.into_bytes(encoding))?,
+        Framing::Binary => {
+            let mut size = 0;
+            for frame in msg.into_frames(encoding) {
+                size += write_frame(stream, &frame)?;
+            }
+            size
+        }
+    };
+
+    match switch {
+        Some(ProtocolOption::Framing(framing)) => encoding.framing = framing,
+        Some(ProtocolOption::Lang(language)) => encoding.language = language,
+        Some(ProtocolOption::Replies(replies)) => encoding.replies = replies,
+        _ => {}
+    }
+    Ok(size)
+}
+
+fn write_frame<W: Write>(stream: &mut W, frame: &[u8]) -> Result<usize, std::io::Error> {
+    stream.write_all(&(frame.len() as u32).to_be_bytes())?;
+    stream.write_all(frame)?;
+
+    Ok(frame.len() + 4)
+}
+
+fn write_line<W: Write>(stream: &mut W, input: &[u8]) -> Result<usize, std::io::Error> {
+    stream.write_all(input)?;
+    stream.write_all(&[10])?;
+
+    Ok(input.len() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::{ClientMessage, Problem};
+    use std::collections::VecDeque;
+
+    // flux en mémoire qui rend les données par morceaux, comme les segments TCP
+    struct Chunks(VecDeque<Vec<u8>>);
+
+    impl Chunks {
+        fn new(chunks: &[&[u8]]) -> Self {
+            Self(chunks.iter().map(|chunk| chunk.to_vec()).collect())
+        }
+    }
+
+    impl Read for Chunks {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let Some(chunk) = self.0.front_mut() else {
+                return Ok(0);
+            };
+            let size = chunk.len().min(buffer.len());
+            buffer[..size].copy_from_slice(&chunk[..size]);
+            chunk.drain(..size);
+            if chunk.is_empty() {
+                self.0.pop_front();
+            }
+            Ok(size)
+        }
+    }
+
+    // messages lus jusqu'à la fin du flux, None pour un message trop long
+    fn lines(stream: &mut Chunks) -> Vec<Option<String>> {
+        let mut reader = MessageReader::new();
+        let mut output = Vec::new();
+        let mut lines = Vec::new();
+        loop {
+            match reader.read(stream, Framing::Text, &mut output).unwrap() {
+                Incoming::Message => lines.push(Some(String::from_utf8(output.clone()).unwrap())),
+                Incoming::Oversized => lines.push(None),
+                Incoming::Closed => return lines,
+            }
+        }
+    }
+
+    fn frame(data: &[u8]) -> Vec<u8> {
+        let mut frame = (data.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(data);
+        frame
+    }
+
+    #[test]
+    fn two_commands_in_one_read() {
+        let mut stream = Chunks::new(&[b"POS\nTICK\n"]);
+        assert_eq!(
+            lines(&mut stream),
+            [Some("POS".into()), Some("TICK".into())]
+        );
+    }
+
+    #[test]
+    fn newline_in_the_middle_of_a_chunk() {
+        let mut stream = Chunks::new(&[b"MOVE U", b"P\nMA", b"P\n"]);
+        assert_eq!(
+            lines(&mut stream),
+            [Some("MOVE UP".into()), Some("MAP".into())]
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let mut stream = Chunks::new(&[b"POS\r\nTICK\r", b"\n\r\n"]);
+        assert_eq!(
+            lines(&mut stream),
+            [Some("POS".into()), Some("TICK".into()), Some("".into())]
+        );
+    }
+
+    #[test]
+    fn leftover_bytes_are_kept_for_the_next_call() {
+        let mut stream = Chunks::new(&[b"POS\nTI", b"CK\n"]);
+        let mut reader = MessageReader::new();
+        let mut output = Vec::new();
+
+        let incoming = reader
+            .read(&mut stream, Framing::Text, &mut output)
+            .unwrap();
+        assert_eq!(
+            (incoming, output.as_slice()),
+            (Incoming::Message, &b"POS"[..])
+        );
+        let incoming = reader
+            .read(&mut stream, Framing::Text, &mut output)
+            .unwrap();
+        assert_eq!(
+            (incoming, output.as_slice()),
+            (Incoming::Message, &b"TICK"[..])
+        );
+        let incoming = reader
+            .read(&mut stream, Framing::Text, &mut output)
+            .unwrap();
+        assert_eq!(incoming, Incoming::Closed);
+    }
+
+    #[test]
+    fn oversized_line_is_reported_once_then_discarded() {
+        let long = vec![b'A'; 3 * MAX_MESSAGE_LENGTH];
+        let mut stream = Chunks::new(&[b"POS\n", &long, b"AAA\nTICK\n"]);
+        assert_eq!(
+            lines(&mut stream),
+            [Some("POS".into()), None, Some("TICK".into())]
+        );
+    }
+
+    #[test]
+    fn oversized_line_received_at_once() {
+        let mut data = vec![b'A'; MAX_MESSAGE_LENGTH + 1];
+        data.extend_from_slice(b"\nTICK\n");
+        let mut stream = Chunks::new(&[&data]);
+        assert_eq!(lines(&mut stream), [None, Some("TICK".into())]);
+    }
+
+    #[test]
+    fn longest_line_is_accepted() {
+        let mut data = vec![b'A'; MAX_MESSAGE_LENGTH];
+        data.push(b'\n');
+        let mut stream = Chunks::new(&[&data]);
+        assert_eq!(
+            lines(&mut stream),
+            [Some(
+                String::from_utf8(data[..MAX_MESSAGE_LENGTH].to_vec()).unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_is_a_protocol_error() {
+        let mut stream = Chunks::new(&[b"MOVE \xff\xfe\n"]);
+        let mut reader = MessageReader::new();
+        let mut output = Vec::new();
+        let incoming = reader
+            .read(&mut stream, Framing::Text, &mut output)
+            .unwrap();
+        assert_eq!(incoming, Incoming::Message);
+        assert!(matches!(
+            ClientMessage::from_bytes(&output),
+            ClientMessage::ProtocolError(Problem::InvalidUtf8)
+        ));
+    }
+
+    #[test]
+    fn switch_to_binary_framing_with_pending_bytes() {
+        let mut data = b"OPTION FRAMING BINARY\n".to_vec();
+        data.extend(frame(b"POS"));
+        data.extend(&frame(b"TICK")[..3]);
+        let mut stream = Chunks::new(&[&data, &frame(b"TICK")[3..]]);
+        let mut reader = MessageReader::new();
+        let mut output = Vec::new();
+
+        let incoming = reader
+            .read(&mut stream, Framing::Text, &mut output)
+            .unwrap();
+        assert_eq!(incoming, Incoming::Message);
+        assert_eq!(output, b"OPTION FRAMING BINARY");
+        let incoming = reader
+            .read(&mut stream, Framing::Binary, &mut output)
+            .unwrap();
+        assert_eq!(
+            (incoming, output.as_slice()),
+            (Incoming::Message, &b"POS"[..])
+        );
+        let incoming = reader
+            .read(&mut stream, Framing::Binary, &mut output)
+            .unwrap();
+        assert_eq!(
+            (incoming, output.as_slice()),
+            (Incoming::Message, &b"TICK"[..])
+        );
+        let incoming = reader
+            .read(&mut stream, Framing::Binary, &mut output)
+            .unwrap();
+        assert_eq!(incoming, Incoming::Closed);
+    }
+
+    #[test]
+    fn binary_frame_longer_than_the_limit() {
+        let mut reader = MessageReader::new();
+        let mut output = Vec::new();
+
+        let mut stream = Chunks::new(&[&frame(&vec![b'A'; MAX_MESSAGE_LENGTH])]);
+        let incoming = reader
+            .read(&mut stream, Framing::Binary, &mut output)
+            .unwrap();
+        assert_eq!(incoming, Incoming::Message);
+        assert_eq!(output.len(), MAX_MESSAGE_LENGTH);
+
+        let mut stream = Chunks::new(&[&frame(&vec![b'A'; MAX_MESSAGE_LENGTH + 1])]);
+        let err = reader
+            .read(&mut stream, Framing::Binary, &mut output)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
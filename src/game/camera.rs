@@ -0,0 +1,191 @@
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::{MouseButton, MouseWheelDirection};
+use sdl2::rect::Rect;
+use std::ops::Range;
+
+// taille d'une case à l'échelle 1, en pixels
+pub const TILE: f32 = 72.0;
+// les images font 24 x 48 pixels et sont agrandies trois fois à l'échelle 1
+const SPRITE_SCALE: f32 = 3.0;
+
+const SCREEN_CENTER: (f32, f32) = (720.0, 405.0);
+const ZOOM_RANGE: (f32, f32) = (0.1, 3.0);
+const ZOOM_STEP: f32 = 1.25;
+
+// ce que la caméra suit : le joueur, ou une des cibles (diamants découverts puis sortie)
+#[derive(Copy, Clone, PartialEq)]
+pub enum Focus {
+    Player,
+    Target(usize),
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Mode {
+    Follow(Focus),
+    Free { x: f32, y: f32 },
+    Fit,
+}
+
+// point de la carte (en pixels à l'échelle 1) affiché au centre de l'écran, et échelle
+#[derive(Copy, Clone)]
+pub struct View {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+}
+
+impl View {
+    pub fn to_screen(self, x: f32, y: f32) -> (i32, i32) {
+        (
+            ((x - self.x) * self.zoom + SCREEN_CENTER.0).floor() as i32,
+            ((y - self.y) * self.zoom + SCREEN_CENTER.1).floor() as i32,
+        )
+    }
+
+    // les lutins font une case de large et deux de haut ; les bords sont calculés
+    // case par case pour qu'il n'y ait pas de jour entre deux cases voisines
+    pub fn sprite_rect(&self, x: f32, y: f32) -> Rect {
+        let (left, top) = self.to_screen(x, y);
+        let (right, bottom) = self.to_screen(x + TILE, y + 2.0 * TILE);
+        Rect::new(
+            left,
+            top,
+            (right - left).max(1) as u32,
+            (bottom - top).max(1) as u32,
+        )
+    }
+
+    pub fn tile_rect(&self, x: u32, y: u32) -> Rect {
+        self.sprite_rect(x as f32 * TILE, y as f32 * TILE)
+    }
+
+    // emplacement d'une suite de cases d'une même ligne, de la colonne start (incluse)
+    // à la colonne end (exclue)
+    pub fn row_rect(&self, columns: &Range<u32>, y: u32) -> Rect {
+        let (left, top) = self.to_screen(columns.start as f32 * TILE, y as f32 * TILE);
+        let (right, bottom) = self.to_screen(columns.end as f32 * TILE, (y + 2) as f32 * TILE);
+        Rect::new(
+            left,
+            top,
+            (right - left).max(1) as u32,
+            (bottom - top).max(1) as u32,
+        )
+    }
+
+    // colonnes et lignes dont les lutins peuvent apparaître à l'écran, sur une carte de
+    // width x height cases ; une case de marge couvre les lutins qui dépassent de leur case
+    // (sur la ligne du dessus, ou en cours de déplacement)
+    pub fn visible_tiles(&self, width: u32, height: u32) -> (Range<u32>, Range<u32>) {
+        let range = |center: f32, half: f32, before: f32, count: u32| {
+            let first = ((center - half / self.zoom) / TILE).floor() - before - 1.0;
+            let last = ((center + half / self.zoom) / TILE).ceil() + 1.0;
+            first.clamp(0.0, count as f32) as u32..last.clamp(0.0, count as f32) as u32
+        };
+        (
+            range(self.x, SCREEN_CENTER.0, 0.0, width),
+            range(self.y, SCREEN_CENTER.1, 1.0, height),
+        )
+    }
+}
+
+// arrondit l'échelle pour que chaque pixel des images couvre un nombre entier de pixels ;
+// en dessous d'un pixel par pixel d'image, ce n'est plus possible et l'échelle est gardée
+pub fn snap_zoom(zoom: f32, fit: bool) -> f32 {
+    let pixels = zoom * SPRITE_SCALE;
+    if pixels < 1.0 {
+        zoom
+    } else if fit {
+        pixels.floor() / SPRITE_SCALE
+    } else {
+        pixels.round() / SPRITE_SCALE
+    }
+}
+
+// caméra des spectateurs : elle ne change ni la partie ni ce qui est envoyé aux clients
+pub struct Camera {
+    mode: Mode,
+    zoom: f32,
+    dragging: bool,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Self {
+            mode: Mode::Follow(Focus::Player),
+            zoom: 1.0,
+            dragging: false,
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    // view : ce qui est affiché actuellement ; targets : nombre de cibles disponibles
+    pub fn handle_event(&mut self, ev: &Event, view: View, targets: usize) {
+        match ev {
+            Event::MouseWheel { y, direction, .. } => {
+                let notches = if *direction == MouseWheelDirection::Flipped {
+                    -*y
+                } else {
+                    *y
+                };
+                self.zoom_by(ZOOM_STEP.powi(notches), view);
+            }
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                ..
+            } => self.dragging = true,
+            Event::MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                ..
+            } => self.dragging = false,
+            Event::MouseMotion { xrel, yrel, .. } if self.dragging => {
+                self.mode = Mode::Free {
+                    x: view.x - *xrel as f32 / view.zoom,
+                    y: view.y - *yrel as f32 / view.zoom,
+                };
+                self.zoom = view.zoom;
+            }
+            Event::KeyDown { keycode, .. } => match keycode {
+                Some(Keycode::F) => self.mode = Mode::Follow(Focus::Player),
+                Some(Keycode::A) => self.mode = Mode::Fit,
+                Some(Keycode::Tab) => {
+                    self.mode = Mode::Follow(match self.mode {
+                        Mode::Follow(Focus::Player) if targets > 0 => Focus::Target(0),
+                        Mode::Follow(Focus::Target(i)) if i + 1 < targets => Focus::Target(i + 1),
+                        _ => Focus::Player,
+                    })
+                }
+                Some(Keycode::KpPlus) | Some(Keycode::Equals) => self.zoom_by(ZOOM_STEP, view),
+                Some(Keycode::KpMinus) | Some(Keycode::Minus) => {
+                    self.zoom_by(1.0 / ZOOM_STEP, view)
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    // l'échelle demandée est gardée telle quelle, même si l'affichage l'arrondit ;
+    // zoomer depuis la vue d'ensemble garde le centre de la carte
+    fn zoom_by(&mut self, factor: f32, view: View) {
+        let zoom = if self.mode == Mode::Fit {
+            view.zoom
+        } else {
+            self.zoom
+        };
+        self.zoom = (zoom * factor).clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+        if self.mode == Mode::Fit {
+            self.mode = Mode::Free {
+                x: view.x,
+                y: view.y,
+            };
+        }
+    }
+}
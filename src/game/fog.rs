@@ -0,0 +1,49 @@
+use super::grid::Grid;
+use super::pos::Pos;
+
+// distance jusqu'à laquelle le joueur découvre les cases autour de lui
+const RADIUS: i32 = 4;
+
+// cases déjà découvertes par le joueur quand le brouillard de guerre est activé ;
+// les autres ne sont ni affichées ni envoyées aux clients
+pub struct Fog {
+    explored: Grid<bool>,
+}
+
+impl Fog {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            explored: Grid::new(width, height, false),
+        }
+    }
+
+    pub fn is_explored(&self, position: Pos) -> bool {
+        self.explored.get(position).unwrap_or(false)
+    }
+
+    // renvoie les cases découvertes par ce déplacement
+    pub fn reveal(&mut self, center: Pos) -> Vec<Pos> {
+        let mut revealed = Vec::new();
+        for dy in -RADIUS..RADIUS + 1 {
+            for dx in -RADIUS..RADIUS + 1 {
+                let x = center.x as i32 + dx;
+                let y = center.y as i32 + dy;
+                if dx * dx + dy * dy > RADIUS * RADIUS
+                    || x < 0
+                    || y < 0
+                    || x >= self.explored.width() as i32
+                    || y >= self.explored.height() as i32
+                {
+                    continue;
+                }
+
+                let position = Pos::new(x as u32, y as u32);
+                if !self.explored[position] {
+                    self.explored[position] = true;
+                    revealed.push(position);
+                }
+            }
+        }
+        revealed
+    }
+}
@@ -0,0 +1,164 @@
+use super::pos::Pos;
+use std::ops::{Index, IndexMut, Range};
+
+// tableau à deux dimensions rangé ligne par ligne, de haut en bas
+#[derive(Clone)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: u32,
+    height: u32,
+}
+
+impl<T: Copy> Grid<T> {
+    pub fn new(width: u32, height: u32, value: T) -> Self {
+        Self {
+            cells: vec![value; (width * height) as usize],
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn contains(&self, position: Pos) -> bool {
+        position.x < self.width && position.y < self.height
+    }
+
+    // None en dehors de la grille
+    pub fn get(&self, position: Pos) -> Option<T> {
+        self.index_of(position).map(|index| self.cells[index])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks n'accepte pas une taille nulle, même quand la grille est vide
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+
+    // remplit le rectangle de colonnes xs et de lignes ys, limité à la grille
+    pub fn fill(&mut self, xs: Range<u32>, ys: Range<u32>, value: T) {
+        // un intervalle vide ou inversé ne remplit rien
+        let end = xs.end.min(self.width);
+        let xs = xs.start.min(end)..end;
+        for y in ys.start.min(self.height)..ys.end.min(self.height) {
+            let row = (y * self.width) as usize;
+            self.cells[row + xs.start as usize..row + xs.end as usize].fill(value);
+        }
+    }
+
+    fn index_of(&self, position: Pos) -> Option<usize> {
+        if self.contains(position) {
+            Some((position.y * self.width + position.x) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Copy> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Pos) -> &T {
+        match self.index_of(position) {
+            Some(index) => &self.cells[index],
+            None => panic!(
+                "case ({}, {}) en dehors de la grille {} x {}",
+                position.x, position.y, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T: Copy> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, position: Pos) -> &mut T {
+        match self.index_of(position) {
+            Some(index) => &mut self.cells[index],
+            None => panic!(
+                "case ({}, {}) en dehors de la grille {} x {}",
+                position.x, position.y, self.width, self.height
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // grille 4 x 3 dont chaque case contient son numéro, ligne par ligne
+    fn numbered() -> Grid<u32> {
+        let mut grid = Grid::new(4, 3, 0);
+        for y in 0..3 {
+            for x in 0..4 {
+                grid[Pos::new(x, y)] = y * 4 + x;
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn get_outside_the_grid() {
+        let grid = numbered();
+        assert_eq!(grid.get(Pos::new(3, 2)), Some(11));
+        assert_eq!(grid.get(Pos::new(4, 0)), None);
+        assert_eq!(grid.get(Pos::new(0, 3)), None);
+        assert_eq!(grid.get(Pos::new(u32::MAX, u32::MAX)), None);
+        assert!(!grid.contains(Pos::new(4, 2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "case (4, 0) en dehors de la grille 4 x 3")]
+    fn index_outside_the_grid() {
+        let grid = numbered();
+        let _ = grid[Pos::new(4, 0)];
+    }
+
+    #[test]
+    #[should_panic(expected = "case (0, 3) en dehors de la grille 4 x 3")]
+    fn index_mut_outside_the_grid() {
+        let mut grid = numbered();
+        grid[Pos::new(0, 3)] = 0;
+    }
+
+    #[test]
+    fn rows_from_top_to_bottom() {
+        let grid = numbered();
+        let rows: Vec<&[u32]> = grid.rows().collect();
+        assert_eq!(rows, [&[0, 1, 2, 3][..], &[4, 5, 6, 7], &[8, 9, 10, 11]]);
+    }
+
+    #[test]
+    fn empty_grid_has_no_rows() {
+        let grid = Grid::new(0, 0, 0);
+        assert_eq!(grid.rows().count(), 0);
+        assert_eq!(grid.get(Pos::new(0, 0)), None);
+    }
+
+    #[test]
+    fn fill_rectangle() {
+        let mut grid = Grid::new(4, 3, '.');
+        grid.fill(1..3, 0..2, '#');
+        let rows: Vec<String> = grid.rows().map(|row| row.iter().collect()).collect();
+        assert_eq!(rows, [".##.", ".##.", "...."]);
+    }
+
+    // les intervalles vides ou inversés sont voulus
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn fill_is_limited_to_the_grid() {
+        let mut grid = Grid::new(4, 3, '.');
+        grid.fill(2..10, 1..10, '#');
+        grid.fill(5..8, 0..3, '!');
+        grid.fill(0..2, 4..6, '!');
+        grid.fill(3..1, 0..3, '!');
+        grid.fill(2..2, 0..3, '!');
+        grid.fill(0..4, 2..0, '!');
+        let rows: Vec<String> = grid.rows().map(|row| row.iter().collect()).collect();
+        assert_eq!(rows, ["....", "..##", "..##"]);
+    }
+}
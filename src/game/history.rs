@@ -0,0 +1,125 @@
+use super::pos::Pos;
+use std::collections::{HashSet, VecDeque};
+
+// nombre de modifications conservées avant d'oublier les plus anciennes
+const CAPACITY: usize = 4096;
+
+// historique des cases modifiées pendant la partie, pour répondre à MAP SINCE
+pub struct History {
+    changes: VecDeque<(u32, Pos)>,
+    complete_since: u32,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            changes: VecDeque::with_capacity(CAPACITY),
+            complete_since: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.changes.clear();
+        self.complete_since = 0;
+    }
+
+    pub fn record(&mut self, tick: u32, position: Pos) {
+        if self.changes.len() == CAPACITY {
+            // les modifications de ce tick ne sont plus toutes connues
+            if let Some((forgotten, _)) = self.changes.pop_front() {
+                self.complete_since = forgotten;
+            }
+        }
+        self.changes.push_back((tick, position));
+    }
+
+    // cases modifiées après le tick donné, ou None si l'historique ne remonte pas assez loin
+    pub fn since(&self, tick: u32) -> Option<Vec<Pos>> {
+        if tick < self.complete_since {
+            return None;
+        }
+
+        // chaque case n'est donnée qu'une fois, dans l'ordre de sa première modification
+        let mut seen = HashSet::new();
+        Some(
+            self.changes
+                .iter()
+                .filter(|(t, position)| *t > tick && seen.insert(*position))
+                .map(|(_, position)| *position)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_after_the_tick_once_each() {
+        let mut history = History::new();
+        history.record(1, Pos::new(1, 1));
+        history.record(2, Pos::new(2, 2));
+        history.record(3, Pos::new(1, 1));
+        history.record(3, Pos::new(3, 3));
+        history.record(4, Pos::new(2, 2));
+
+        assert_eq!(
+            history.since(0),
+            Some(vec![Pos::new(1, 1), Pos::new(2, 2), Pos::new(3, 3)])
+        );
+        assert_eq!(
+            history.since(2),
+            Some(vec![Pos::new(1, 1), Pos::new(3, 3), Pos::new(2, 2)])
+        );
+        assert_eq!(history.since(4), Some(vec![]));
+        assert_eq!(history.since(10), Some(vec![]));
+    }
+
+    #[test]
+    fn full_history_up_to_capacity() {
+        let mut history = History::new();
+        for i in 0..CAPACITY as u32 {
+            history.record(i + 1, Pos::new(i % 64, i / 64));
+        }
+        assert_eq!(
+            history.since(0).map(|positions| positions.len()),
+            Some(CAPACITY)
+        );
+    }
+
+    #[test]
+    fn too_old_tick_falls_back_to_a_snapshot() {
+        let mut history = History::new();
+        for i in 0..CAPACITY as u32 + 1 {
+            history.record(i + 1, Pos::new(i % 64, i / 64));
+        }
+        // la modification du tick 1 est oubliée : seuls les ticks suivants sont complets
+        assert_eq!(history.since(0), None);
+        assert_eq!(
+            history.since(1).map(|positions| positions.len()),
+            Some(CAPACITY)
+        );
+    }
+
+    #[test]
+    fn tick_partly_forgotten() {
+        let mut history = History::new();
+        for i in 0..CAPACITY as u32 {
+            history.record(1, Pos::new(i % 64, i / 64));
+        }
+        history.record(2, Pos::new(0, 100));
+        assert_eq!(history.since(0), None);
+        assert_eq!(history.since(1), Some(vec![Pos::new(0, 100)]));
+    }
+
+    #[test]
+    fn clear_forgets_everything() {
+        let mut history = History::new();
+        for i in 0..CAPACITY as u32 + 1 {
+            history.record(i + 1, Pos::new(0, 0));
+        }
+        history.clear();
+        assert_eq!(history.since(0), Some(vec![]));
+    }
+}
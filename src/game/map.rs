@@ -1,7 +1,8 @@
+use super::grid::Grid;
 use super::pos::{Direction, Pos};
+use crate::settings::{Difficulty, Generator};
 use oorandom::Rand32;
 use std::cmp::Ordering;
-use std::collections::HashMap;
 
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub enum Tile {
@@ -20,178 +21,107 @@ struct Diamond {
     pos: Pos,
 }
 
+// taille de référence des cartes, pour laquelle les quantités de pierres sont données
+const REFERENCE_SIZE: u32 = 37;
+
 pub struct Map {
-    terrain: HashMap<Pos, Tile>,
+    terrain: Grid<Tile>,
     player: Player,
     diamonds: Vec<Diamond>,
 }
 
 impl Map {
-    pub fn generate(seed: u32) -> Self {
-        let mut rng = Rand32::new(seed.into());
-        let mut terrain = HashMap::new();
-
-        for x in 1..36 {
-            for y in 1..36 {
-                terrain.insert(Pos::new(x, y), Tile::WALL);
-            }
-        }
-
-        for x in 0..36 {
-            terrain.insert(Pos::new(x, 0), Tile::BORDER);
-        }
-        for y in 0..36 {
-            terrain.insert(Pos::new(36, y), Tile::BORDER);
-        }
-        for x in (1..37).rev() {
-            terrain.insert(Pos::new(x, 36), Tile::BORDER);
-        }
-        for y in (1..37).rev() {
-            terrain.insert(Pos::new(0, y), Tile::BORDER);
-        }
-
-        for _ in 0..rng.rand_range(10..40) {
-            terrain.insert(
-                Pos::new(rng.rand_range(2..35), rng.rand_range(2..35)),
-                Tile::STONE,
-            );
-        }
-
-        let room_count = rng.rand_range(4..7);
-        let mut rooms_center = Vec::with_capacity(7);
-        for _ in 0..room_count {
-            let top = rng.rand_range(2..25);
-            let left = rng.rand_range(2..25);
-
-            rooms_center.push((left + 5, top + 5));
-
-            for x in 1..10 {
-                for y in 1..10 {
-                    terrain.insert(Pos::new(left + x, top + y), Tile::EMPTY);
-                }
-            }
-
-            if rng.rand_float() < 0.5 {
-                for y in 2..9 {
-                    terrain.insert(Pos::new(left, top + y), Tile::EMPTY);
-                    terrain.insert(Pos::new(left + 10, top + y), Tile::EMPTY);
-                }
-            }
-
-            if rng.rand_float() < 0.5 {
-                for x in 2..9 {
-                    terrain.insert(Pos::new(left + x, top), Tile::EMPTY);
-                    terrain.insert(Pos::new(left + x, top + 10), Tile::EMPTY);
-                }
+    // les cartes sont carrées, de taille impaire pour que la sortie soit au centre
+    pub fn generate(seed: u64, size: u32, generator: Generator, difficulty: Difficulty) -> Self {
+        let mut rng = Rand32::new(seed);
+        let mut terrain = Grid::new(size, size, Tile::BORDER);
+        let last = size - 1;
+        let center = size / 2;
+
+        terrain.fill(1..last, 1..last, Tile::WALL);
+
+        let rooms_center = match generator {
+            Generator::Rooms => Some(dig_rooms(&mut rng, &mut terrain, size, difficulty)),
+            Generator::Caves => {
+                dig_caves(&mut rng, &mut terrain, size, difficulty);
+                None
             }
-        }
-
-        for c in &rooms_center {
-            terrain.insert(Pos::from(*c), Tile::BORDER);
-        }
-
-        for i in 0..room_count {
-            for j in i + 1..room_count {
-                let i = i as usize;
-                let j = j as usize;
-                if rng.rand_float() < 0.5 {
-                    if rooms_center[i].0 < rooms_center[j].0 {
-                        for x in rooms_center[i].0..rooms_center[j].0 + 1 {
-                            terrain.insert(Pos::new(x, rooms_center[i].1), Tile::EMPTY);
-                        }
-                    } else {
-                        for x in rooms_center[j].0..rooms_center[i].0 + 1 {
-                            terrain.insert(Pos::new(x, rooms_center[i].1), Tile::EMPTY);
-                        }
-                    }
-                    if rooms_center[i].1 < rooms_center[j].1 {
-                        for y in rooms_center[i].1..rooms_center[j].1 + 1 {
-                            terrain.insert(Pos::new(rooms_center[j].0, y), Tile::EMPTY);
-                        }
-                    } else {
-                        for y in rooms_center[j].1..rooms_center[i].1 + 1 {
-                            terrain.insert(Pos::new(rooms_center[j].0, y), Tile::EMPTY);
-                        }
-                    }
-                }
-            }
-        }
+        };
 
         let mut diamonds = Vec::new();
 
-        for _ in 0..10 {
-            let d = Diamond::new(rng.rand_range(2..35), rng.rand_range(2..35));
+        for _ in 0..diamonds_count(difficulty) {
+            let d = Diamond::new(rng.rand_range(2..size - 2), rng.rand_range(2..size - 2));
 
-            if terrain[&d.position().into()] != Tile::EMPTY {
-                if terrain[&d.position().moved(Direction::UP).into()] != Tile::EMPTY
-                    && terrain[&d.position().moved(Direction::LEFT).into()] != Tile::EMPTY
-                    && terrain[&d.position().moved(Direction::DOWN).into()] != Tile::EMPTY
-                    && terrain[&d.position().moved(Direction::RIGHT).into()] != Tile::EMPTY
+            if terrain[d.position()] != Tile::EMPTY {
+                if terrain[d.position().moved(Direction::UP)] != Tile::EMPTY
+                    && terrain[d.position().moved(Direction::LEFT)] != Tile::EMPTY
+                    && terrain[d.position().moved(Direction::DOWN)] != Tile::EMPTY
+                    && terrain[d.position().moved(Direction::RIGHT)] != Tile::EMPTY
                 {
-                    for x in 0..3 {
-                        for y in 0..3 {
-                            terrain.insert(
-                                Pos::new(d.position().x + x - 1, d.position().y + y - 1),
-                                Tile::EMPTY,
-                            );
-                        }
-                    }
+                    terrain.fill(
+                        d.position().x - 1..d.position().x + 2,
+                        d.position().y - 1..d.position().y + 2,
+                        Tile::EMPTY,
+                    );
                 } else {
-                    terrain.insert(Pos::new(d.position().x, d.position().y), Tile::EMPTY);
+                    terrain[Pos::new(d.position().x, d.position().y)] = Tile::EMPTY;
                 }
             }
 
             diamonds.push(d);
         }
 
-        if terrain[&Pos::new(18, 18)] != Tile::EMPTY {
-            let nearest = rooms_center
-                .iter()
-                .enumerate()
-                .map(|(index, item)| {
-                    (
-                        index,
-                        (((item.0 as i32 - 18).pow(2) + (item.1 as i32 - 18).pow(2)) as f32).sqrt(),
-                    )
-                })
-                .min_by(|(_, dist1), (_, dist2)| {
-                    dist1.partial_cmp(dist2).unwrap_or(Ordering::Equal)
-                })
-                .unwrap()
-                .0;
-            if rooms_center[nearest].0 < 18 {
-                for x in rooms_center[nearest].0..18 + 1 {
-                    terrain.insert(Pos::new(x, rooms_center[nearest].1), Tile::EMPTY);
-                }
-            } else {
-                for x in 18..rooms_center[nearest].0 + 1 {
-                    terrain.insert(Pos::new(x, rooms_center[nearest].1), Tile::EMPTY);
-                }
-            }
-            if rooms_center[nearest].1 < 18 {
-                for y in rooms_center[nearest].1..18 + 1 {
-                    terrain.insert(Pos::new(18, y), Tile::EMPTY);
+        match rooms_center {
+            Some(rooms_center) if terrain[Pos::new(center, center)] != Tile::EMPTY => {
+                let nearest = rooms_center
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        (
+                            index,
+                            (((item.0 as i32 - center as i32).pow(2)
+                                + (item.1 as i32 - center as i32).pow(2))
+                                as f32)
+                                .sqrt(),
+                        )
+                    })
+                    .min_by(|(_, dist1), (_, dist2)| {
+                        dist1.partial_cmp(dist2).unwrap_or(Ordering::Equal)
+                    })
+                    .unwrap()
+                    .0;
+                if rooms_center[nearest].0 < center {
+                    for x in rooms_center[nearest].0..center + 1 {
+                        terrain[Pos::new(x, rooms_center[nearest].1)] = Tile::EMPTY;
+                    }
+                } else {
+                    for x in center..rooms_center[nearest].0 + 1 {
+                        terrain[Pos::new(x, rooms_center[nearest].1)] = Tile::EMPTY;
+                    }
                 }
-            } else {
-                for y in 18..rooms_center[nearest].1 + 1 {
-                    terrain.insert(Pos::new(18, y), Tile::EMPTY);
+                if rooms_center[nearest].1 < center {
+                    for y in rooms_center[nearest].1..center + 1 {
+                        terrain[Pos::new(center, y)] = Tile::EMPTY;
+                    }
+                } else {
+                    for y in center..rooms_center[nearest].1 + 1 {
+                        terrain[Pos::new(center, y)] = Tile::EMPTY;
+                    }
                 }
             }
+            _ => {}
         }
 
-        for x in 17..20 {
-            for y in 17..20 {
-                terrain.insert(Pos::new(x, y), Tile::EMPTY);
-            }
-        }
+        terrain.fill(center - 1..center + 2, center - 1..center + 2, Tile::EMPTY);
 
-        terrain.insert(Pos::new(18, 18), Tile::EXIT);
+        terrain[Pos::new(center, center)] = Tile::EXIT;
 
         let mut player_pos = Pos::new(0, 0);
 
-        while terrain[&player_pos] != Tile::EMPTY {
-            player_pos.x = rng.rand_range(4..33);
-            player_pos.y = rng.rand_range(4..33);
+        while terrain[player_pos] != Tile::EMPTY {
+            player_pos.x = rng.rand_range(4..size - 4);
+            player_pos.y = rng.rand_range(4..size - 4);
         }
 
         Self {
@@ -203,38 +133,103 @@ impl Map {
 
     pub fn empty() -> Self {
         Self {
-            terrain: HashMap::new(),
+            terrain: Grid::new(0, 0, Tile::BORDER),
             player: Player::new(0, 0),
             diamonds: Vec::new(),
         }
     }
 
     pub fn repr(&self) -> String {
-        let mut string = String::with_capacity(1369);
+        let mut letters: Vec<char> = self
+            .terrain
+            .rows()
+            .flatten()
+            .map(|tile| tile_letter(*tile))
+            .collect();
+        for d in &self.diamonds {
+            letters[(d.position().y * self.width() + d.position().x) as usize] = 'D';
+        }
+        let player = self.player.position();
+        letters[(player.y * self.width() + player.x) as usize] = 'J';
 
-        for y in 0..37 {
-            'lbl: for x in 0..37 {
-                if self.player.position() == (x, y) {
-                    string.push('J');
-                    continue;
-                }
-                for d in &self.diamonds {
-                    if d.position() == (x, y) {
-                        string.push('D');
-                        continue 'lbl;
-                    }
-                }
-                string.push(match self.terrain.get(&Pos::new(x, y)).unwrap() {
-                    Tile::EMPTY => ' ',
-                    Tile::WALL => 'M',
-                    Tile::STONE => 'P',
-                    Tile::BORDER => 'B',
-                    Tile::EXIT => 'S',
-                });
+        letters.into_iter().collect()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.terrain.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.terrain.height()
+    }
+
+    // caractère représentant une case dans la réponse à MAP
+    pub fn letter_at(&self, position: Pos) -> char {
+        if self.player.position() == position {
+            return 'J';
+        }
+        for d in &self.diamonds {
+            if d.position() == position {
+                return 'D';
+            }
+        }
+        tile_letter(self.terrain[position])
+    }
+
+    // encodage binaire décrit dans PROTOCOLE.md ; les cases inexplorées sont cachées
+    pub fn encode_binary<F: Fn(Pos) -> bool>(&self, explored: F) -> Vec<u8> {
+        let width = self.width();
+        let height = self.height();
+        let tiles_count = (width * height) as usize;
+
+        let mut data =
+            Vec::with_capacity(6 + tiles_count.div_ceil(2) + 5 * (self.diamonds.len() + 2));
+        data.extend_from_slice(&(width as u16).to_be_bytes());
+        data.extend_from_slice(&(height as u16).to_be_bytes());
+
+        let mut exits = Vec::new();
+        let mut packed = 0;
+        for i in 0..tiles_count {
+            let position = Pos::new(i as u32 % width, i as u32 / width);
+            let tile = self.tile_at(position).unwrap_or(Tile::BORDER);
+            if tile == Tile::EXIT && explored(position) {
+                exits.push(position);
             }
+            let code = match tile {
+                _ if !explored(position) => 5,
+                Tile::EMPTY => 0,
+                Tile::WALL => 1,
+                Tile::STONE => 2,
+                Tile::BORDER => 3,
+                Tile::EXIT => 4,
+            };
+            if i % 2 == 0 {
+                packed = code << 4;
+            } else {
+                data.push(packed | code);
+            }
+        }
+        if tiles_count % 2 == 1 {
+            data.push(packed);
+        }
+
+        let mut entities = vec![(0, self.player.position())];
+        entities.extend(
+            self.diamonds
+                .iter()
+                .map(|d| (1, d.position()))
+                .filter(|(_, position)| explored(*position)),
+        );
+        entities.extend(exits.into_iter().map(|e| (2, e)));
+
+        data.extend_from_slice(&(entities.len() as u16).to_be_bytes());
+        for (kind, position) in entities {
+            data.push(kind);
+            data.extend_from_slice(&(position.x as u16).to_be_bytes());
+            data.extend_from_slice(&(position.y as u16).to_be_bytes());
         }
 
-        string
+        data
     }
 
     pub fn move_player(&mut self, direction: Direction) {
@@ -245,11 +240,13 @@ impl Map {
         self.player.pos
     }
 
+    // la sortie est toujours au centre de la carte
+    pub fn exit_pos(&self) -> Pos {
+        Pos::new(self.width() / 2, self.height() / 2)
+    }
+
     pub fn tile_at(&self, position: Pos) -> Option<Tile> {
-        match self.terrain.get(&position) {
-            Some(tile) => Some(*tile),
-            None => None,
-        }
+        self.terrain.get(position)
     }
 
     pub fn diamonds_pos(&self) -> Vec<Pos> {
@@ -261,19 +258,156 @@ impl Map {
     }
 
     pub fn dig(&mut self, position: Pos) {
-        self.terrain.insert(position, Tile::EMPTY);
+        self.terrain[position] = Tile::EMPTY;
     }
 
-    pub fn pick_up_diamond(&mut self) {
+    pub fn pick_up_diamond(&mut self) -> bool {
         for i in 0..self.diamonds.len() {
             if self.diamonds[i].position() == self.player_pos() {
                 self.diamonds.remove(i);
-                break;
+                return true;
             }
         }
+        false
+    }
+}
+
+fn tile_letter(tile: Tile) -> char {
+    match tile {
+        Tile::EMPTY => ' ',
+        Tile::WALL => 'M',
+        Tile::STONE => 'P',
+        Tile::BORDER => 'B',
+        Tile::EXIT => 'S',
+    }
+}
+
+fn diamonds_count(difficulty: Difficulty) -> u32 {
+    match difficulty {
+        Difficulty::Easy => 5,
+        Difficulty::Normal => 10,
+        Difficulty::Hard => 15,
     }
 }
 
+// le nombre de pierres est proportionnel à la surface de la carte
+fn place_stones(rng: &mut Rand32, terrain: &mut Grid<Tile>, size: u32, difficulty: Difficulty) {
+    let (min, max) = match difficulty {
+        Difficulty::Easy => (5, 20),
+        Difficulty::Normal => (10, 40),
+        Difficulty::Hard => (20, 80),
+    };
+    let area = size * size;
+    let reference = REFERENCE_SIZE * REFERENCE_SIZE;
+
+    for _ in 0..rng.rand_range(min * area / reference..max * area / reference) {
+        terrain[Pos::new(rng.rand_range(2..size - 2), rng.rand_range(2..size - 2))] = Tile::STONE;
+    }
+}
+
+// salles carrées reliées par des couloirs ; le centre de chaque salle est un pilier
+fn dig_rooms(
+    rng: &mut Rand32,
+    terrain: &mut Grid<Tile>,
+    size: u32,
+    difficulty: Difficulty,
+) -> Vec<(u32, u32)> {
+    place_stones(rng, terrain, size, difficulty);
+
+    let room_count = rng.rand_range(4..7);
+    let mut rooms_center = Vec::with_capacity(7);
+    for _ in 0..room_count {
+        let top = rng.rand_range(2..size - 12);
+        let left = rng.rand_range(2..size - 12);
+
+        rooms_center.push((left + 5, top + 5));
+
+        terrain.fill(left + 1..left + 10, top + 1..top + 10, Tile::EMPTY);
+
+        if rng.rand_float() < 0.5 {
+            for y in 2..9 {
+                terrain[Pos::new(left, top + y)] = Tile::EMPTY;
+                terrain[Pos::new(left + 10, top + y)] = Tile::EMPTY;
+            }
+        }
+
+        if rng.rand_float() < 0.5 {
+            for x in 2..9 {
+                terrain[Pos::new(left + x, top)] = Tile::EMPTY;
+                terrain[Pos::new(left + x, top + 10)] = Tile::EMPTY;
+            }
+        }
+    }
+
+    for c in &rooms_center {
+        terrain[Pos::from(*c)] = Tile::BORDER;
+    }
+
+    for i in 0..room_count {
+        for j in i + 1..room_count {
+            let i = i as usize;
+            let j = j as usize;
+            if rng.rand_float() < 0.5 {
+                if rooms_center[i].0 < rooms_center[j].0 {
+                    for x in rooms_center[i].0..rooms_center[j].0 + 1 {
+                        terrain[Pos::new(x, rooms_center[i].1)] = Tile::EMPTY;
+                    }
+                } else {
+                    for x in rooms_center[j].0..rooms_center[i].0 + 1 {
+                        terrain[Pos::new(x, rooms_center[i].1)] = Tile::EMPTY;
+                    }
+                }
+                if rooms_center[i].1 < rooms_center[j].1 {
+                    for y in rooms_center[i].1..rooms_center[j].1 + 1 {
+                        terrain[Pos::new(rooms_center[j].0, y)] = Tile::EMPTY;
+                    }
+                } else {
+                    for y in rooms_center[j].1..rooms_center[i].1 + 1 {
+                        terrain[Pos::new(rooms_center[j].0, y)] = Tile::EMPTY;
+                    }
+                }
+            }
+        }
+    }
+
+    rooms_center
+}
+
+// cavernes obtenues par un automate cellulaire : une case devient un mur
+// quand au moins 5 de ses 8 voisines en sont
+fn dig_caves(rng: &mut Rand32, terrain: &mut Grid<Tile>, size: u32, difficulty: Difficulty) {
+    for x in 1..size - 1 {
+        for y in 1..size - 1 {
+            let tile = if rng.rand_float() < 0.45 {
+                Tile::WALL
+            } else {
+                Tile::EMPTY
+            };
+            terrain[Pos::new(x, y)] = tile;
+        }
+    }
+
+    for _ in 0..4 {
+        let previous = terrain.clone();
+        for x in 1..size - 1 {
+            for y in 1..size - 1 {
+                let mut walls = 0;
+                for nx in x - 1..x + 2 {
+                    for ny in y - 1..y + 2 {
+                        if (nx, ny) != (x, y) && previous[Pos::new(nx, ny)] != Tile::EMPTY {
+                            walls += 1;
+                        }
+                    }
+                }
+                let tile = if walls >= 5 { Tile::WALL } else { Tile::EMPTY };
+                terrain[Pos::new(x, y)] = tile;
+            }
+        }
+    }
+
+    place_stones(rng, terrain, size, difficulty);
+}
+
 impl Player {
     pub fn new(x: u32, y: u32) -> Self {
         Self {
@@ -309,3 +443,175 @@ impl Entity for Diamond {
         self.pos
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // lettres des codes de case, dans l'ordre du tableau de PROTOCOLE.md
+    const LETTERS: [char; 6] = [' ', 'M', 'P', 'B', 'S', '?'];
+
+    struct Decoded {
+        width: u32,
+        height: u32,
+        letters: Vec<char>,
+        entities: Vec<(u8, Pos)>,
+    }
+
+    fn u16_at(data: &[u8], offset: usize) -> u32 {
+        u16::from_be_bytes([data[offset], data[offset + 1]]) as u32
+    }
+
+    // décodage suivant PROTOCOLE.md, indépendant de encode_binary
+    fn decode(data: &[u8]) -> Decoded {
+        let width = u16_at(data, 0);
+        let height = u16_at(data, 2);
+        let count = (width * height) as usize;
+        let mut letters = Vec::with_capacity(count);
+        for i in 0..count {
+            let byte = data[4 + i / 2];
+            let code = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+            letters.push(LETTERS[code as usize]);
+        }
+        if count % 2 == 1 {
+            assert_eq!(
+                data[4 + count / 2] & 0x0f,
+                0,
+                "bits de remplissage non nuls"
+            );
+        }
+
+        let mut offset = 4 + count.div_ceil(2);
+        let entities_count = u16_at(data, offset) as usize;
+        offset += 2;
+        let mut entities = Vec::with_capacity(entities_count);
+        for _ in 0..entities_count {
+            let position = Pos::new(u16_at(data, offset + 1), u16_at(data, offset + 3));
+            entities.push((data[offset], position));
+            offset += 5;
+        }
+        assert_eq!(offset, data.len(), "octets en trop après les entités");
+
+        Decoded {
+            width,
+            height,
+            letters,
+            entities,
+        }
+    }
+
+    // carte texte reconstruite comme l'indique PROTOCOLE.md : les diamants, puis le joueur
+    fn to_text(decoded: &Decoded) -> String {
+        let mut letters = decoded.letters.clone();
+        for kind in [1, 0] {
+            for (_, position) in decoded.entities.iter().filter(|(k, _)| *k == kind) {
+                letters[(position.y * decoded.width + position.x) as usize] =
+                    if kind == 0 { 'J' } else { 'D' };
+            }
+        }
+        letters.into_iter().collect()
+    }
+
+    fn maps() -> Vec<Map> {
+        let mut maps = Vec::new();
+        for seed in 0..8 {
+            for size in [25, 37, 51] {
+                for generator in [Generator::Rooms, Generator::Caves] {
+                    maps.push(Map::generate(seed, size, generator, Difficulty::Normal));
+                }
+            }
+        }
+        maps
+    }
+
+    fn assert_round_trip<F: Fn(Pos) -> bool>(map: &Map, explored: F) {
+        let decoded = decode(&map.encode_binary(&explored));
+        assert_eq!((decoded.width, decoded.height), (map.width(), map.height()));
+
+        let expected: String = map
+            .repr()
+            .chars()
+            .enumerate()
+            .map(|(i, letter)| {
+                let position = Pos::new(i as u32 % map.width(), i as u32 / map.width());
+                if explored(position) {
+                    letter
+                } else {
+                    '?'
+                }
+            })
+            .collect();
+        let decoded_text = to_text(&decoded);
+        for (i, (got, want)) in decoded_text.chars().zip(expected.chars()).enumerate() {
+            assert_eq!(
+                got,
+                want,
+                "case ({}, {})",
+                i as u32 % map.width(),
+                i as u32 / map.width()
+            );
+        }
+        assert_eq!(decoded_text.len(), expected.len());
+
+        assert_eq!(decoded.entities[0], (0, map.player_pos()));
+        let diamonds: Vec<Pos> = map
+            .diamonds_pos()
+            .into_iter()
+            .filter(|position| explored(*position))
+            .collect();
+        let decoded_diamonds: Vec<Pos> = decoded
+            .entities
+            .iter()
+            .filter(|(kind, _)| *kind == 1)
+            .map(|(_, position)| *position)
+            .collect();
+        assert_eq!(decoded_diamonds, diamonds);
+        let exits: Vec<Pos> = decoded
+            .entities
+            .iter()
+            .filter(|(kind, _)| *kind == 2)
+            .map(|(_, position)| *position)
+            .collect();
+        if explored(map.exit_pos()) {
+            assert_eq!(exits, vec![map.exit_pos()]);
+        } else {
+            assert!(exits.is_empty());
+        }
+        assert_eq!(decoded.entities.len(), 1 + diamonds.len() + exits.len());
+    }
+
+    #[test]
+    fn binary_round_trip_without_fog() {
+        for map in maps() {
+            assert_round_trip(&map, |_| true);
+        }
+    }
+
+    #[test]
+    fn binary_round_trip_with_fog() {
+        for map in maps() {
+            let player = map.player_pos();
+            // une case sur trois cachée, ainsi que la sortie ; le joueur reste visible
+            assert_round_trip(&map, |position| {
+                position == player
+                    || (position != map.exit_pos() && (position.x + 2 * position.y) % 3 != 0)
+            });
+        }
+    }
+
+    #[test]
+    fn binary_round_trip_player_on_diamond() {
+        let mut map = Map::generate(3, 37, Generator::Rooms, Difficulty::Easy);
+        map.player.pos = map.diamonds_pos()[0];
+        assert_round_trip(&map, |_| true);
+        assert_eq!(map.letter_at(map.player_pos()), 'J');
+    }
+
+    #[test]
+    fn binary_empty_map() {
+        let map = Map::empty();
+        let decoded = decode(&map.encode_binary(|_| true));
+        assert_eq!((decoded.width, decoded.height), (0, 0));
+        assert_eq!(decoded.entities, vec![(0, Pos::new(0, 0))]);
+    }
+}
@@ -0,0 +1,105 @@
+use super::map::{Map, Tile};
+use super::pos::Pos;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget};
+use std::collections::VecDeque;
+
+// informations ajoutées à la minicarte pendant une partie
+pub struct Overlay<'a> {
+    pub dug: &'a [Pos],
+    pub trail: &'a VecDeque<Pos>,
+}
+
+// dessine la carte dans un carré de `size` pixels, une case par bloc de pixels ;
+// renvoie le rectangle effectivement occupé
+pub fn draw<T, F>(
+    canvas: &mut Canvas<T>,
+    map: &Map,
+    area: (i32, i32, u32),
+    explored: F,
+    overlay: Option<Overlay>,
+) -> Rect
+where
+    T: RenderTarget,
+    F: Fn(Pos) -> bool,
+{
+    let cell = (area.2 / map.width().max(1)).max(1);
+    let origin_x = area.0 + (area.2 as i32 - (cell * map.width()) as i32) / 2;
+    let origin_y = area.1 + (area.2 as i32 - (cell * map.height()) as i32) / 2;
+    let rect = |pos: Pos| {
+        Rect::new(
+            origin_x + (pos.x * cell) as i32,
+            origin_y + (pos.y * cell) as i32,
+            cell,
+            cell,
+        )
+    };
+
+    // les cases sont regroupées par couleur pour limiter les appels de dessin
+    let mut layers: [(Color, Vec<Rect>); 5] = [
+        (Color::RGB(40, 32, 48), Vec::new()),
+        (Color::RGB(120, 84, 52), Vec::new()),
+        (Color::RGB(128, 128, 128), Vec::new()),
+        (Color::RGB(16, 16, 16), Vec::new()),
+        (Color::RGB(40, 200, 80), Vec::new()),
+    ];
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            let pos = Pos::new(x, y);
+            if !explored(pos) {
+                continue;
+            }
+            let layer = match map.tile_at(pos) {
+                Some(Tile::EMPTY) => 0,
+                Some(Tile::WALL) => 1,
+                Some(Tile::STONE) => 2,
+                Some(Tile::BORDER) | None => 3,
+                Some(Tile::EXIT) => 4,
+            };
+            layers[layer].1.push(rect(pos));
+        }
+    }
+    for (color, rects) in &layers {
+        canvas.set_draw_color(*color);
+        canvas.fill_rects(rects).unwrap();
+    }
+
+    if let Some(overlay) = overlay {
+        canvas.set_draw_color(Color::RGB(200, 150, 90));
+        let dug: Vec<Rect> = overlay.dug.iter().map(|pos| rect(*pos)).collect();
+        canvas.fill_rects(&dug).unwrap();
+
+        // le chemin est dessiné plus petit que les cases pour laisser voir le terrain
+        canvas.set_draw_color(Color::RGB(255, 140, 0));
+        let margin = cell as i32 / 4;
+        let trail: Vec<Rect> = overlay
+            .trail
+            .iter()
+            .map(|pos| {
+                let r = rect(*pos);
+                Rect::new(
+                    r.x() + margin,
+                    r.y() + margin,
+                    (cell as i32 - 2 * margin).max(1) as u32,
+                    (cell as i32 - 2 * margin).max(1) as u32,
+                )
+            })
+            .collect();
+        canvas.fill_rects(&trail).unwrap();
+    }
+
+    canvas.set_draw_color(Color::RGB(80, 220, 255));
+    let diamonds: Vec<Rect> = map
+        .diamonds_pos()
+        .into_iter()
+        .filter(|pos| explored(*pos))
+        .map(rect)
+        .collect();
+    canvas.fill_rects(&diamonds).unwrap();
+    canvas.set_draw_color(Color::RGB(255, 255, 255));
+    canvas.fill_rect(rect(map.player_pos())).unwrap();
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+
+    Rect::new(origin_x, origin_y, cell * map.width(), cell * map.height())
+}
@@ -1,23 +1,49 @@
-use crate::interface::{ClientMessage, ServerMessage};
+use crate::i18n::{Language, Text};
+use crate::interface::{
+    ClientMessage, EventKind, Framing, GameEvent, MapData, MapFormat, Outcome, Problem,
+    ProtocolOption, ServerMessage, Step,
+};
 use crate::resource::image::Images;
-use crate::resource::text::TextRenderer;
-use crate::resource::text::TextRenderingFormat::{Blended, Shaded};
+use crate::resource::text::TextRenderingFormat::{Outlined, Shaded};
+use crate::resource::text::{Anchor, FontSize, TextRenderer, TextStyle};
+use crate::settings::{Rhythm, Settings};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::{Canvas, RenderTarget};
+use std::collections::{HashSet, VecDeque};
 
+pub mod camera;
+pub mod fog;
+pub mod grid;
+pub mod history;
 pub mod map;
+pub mod minimap;
 pub mod pos;
+pub mod terrain;
 
+use camera::{snap_zoom, Camera, Focus, Mode, View, TILE};
+use fog::Fog;
+use history::History;
 use map::{Map, Tile};
 use pos::{Direction, Pos};
+use terrain::TerrainCache;
 
 enum Action {
     Nothing,
     PlayerMovement(Direction),
     DigWall(Pos),
+    Wait,
+}
+
+// nombre de cases gardées dans la trace du joueur sur la minicarte
+const TRAIL_LENGTH: usize = 64;
+
+// séquence d'actions envoyée avec PATH, exécutée à raison d'une action par temps
+struct Path {
+    steps: VecDeque<Step>,
+    done: usize,
 }
 
 #[derive(PartialEq)]
@@ -27,14 +53,41 @@ enum State {
     WON,
 }
 
+impl State {
+    fn name(&self) -> &'static str {
+        match self {
+            State::PLAYING => "PLAYING",
+            State::STOPPED => "STOPPED",
+            State::WON => "WON",
+        }
+    }
+}
+
 pub struct Game {
     map: Map,
+    diamonds_total: usize,
     diamonds_left: usize,
     animations_step_1: u8, // pour les animations des lutins
     animations_step_2: u8, // pour les animations de mouvement
     timer_end: u8,
-    response: Option<ServerMessage>,
+    tick: u32,
+    responses: VecDeque<ServerMessage>,
     reaction: Action,
+    path: Option<Path>,
+    subscriptions: Vec<EventKind>,
+    history: History,
+    map_format: MapFormat,
+    framing: Framing,
+    fog: Option<Fog>,
+    rhythm: Rhythm,
+    dug: Vec<Pos>,
+    trail: VecDeque<Pos>,
+    show_minimap: bool,
+    show_trail: bool,
+    camera: Camera,
+    integer_scaling: bool,
+    // change à chaque modification du terrain affiché, pour reconstruire son cache
+    terrain_version: u32,
     player_sprite_direction: Direction,
     state: State,
 }
@@ -43,166 +96,551 @@ impl Game {
     pub fn new() -> Self {
         Self {
             map: Map::empty(),
+            diamonds_total: 0,
             diamonds_left: 0,
             animations_step_1: 0,
             animations_step_2: 0,
-            response: None,
+            responses: VecDeque::new(),
             reaction: Action::Nothing,
+            path: None,
+            subscriptions: Vec::new(),
+            history: History::new(),
+            map_format: MapFormat::Rows,
+            framing: Framing::Text,
+            fog: None,
+            rhythm: Rhythm::Free,
+            dug: Vec::new(),
+            trail: VecDeque::new(),
+            show_minimap: true,
+            show_trail: false,
+            camera: Camera::new(),
+            integer_scaling: false,
+            terrain_version: 0,
             player_sprite_direction: Direction::RIGHT,
             state: State::STOPPED,
             timer_end: 0,
+            tick: 0,
         }
     }
 
-    pub fn reset(&mut self, seed: u32) {
-        self.map = Map::generate(seed);
-        self.diamonds_left = self.map.diamonds_count();
+    pub fn reset(&mut self, seed: u64, settings: &Settings) {
+        info!("nouvelle partie (graine {})", seed);
+        self.map = Map::generate(
+            seed,
+            settings.map_size,
+            settings.generator,
+            settings.difficulty,
+        );
+        self.diamonds_total = self.map.diamonds_count();
+        self.diamonds_left = self.diamonds_total;
+        self.fog = if settings.fog {
+            let mut fog = Fog::new(self.map.width(), self.map.height());
+            fog.reveal(self.map.player_pos());
+            Some(fog)
+        } else {
+            None
+        };
+        self.rhythm = settings.rhythm;
+        self.dug.clear();
+        self.trail.clear();
+        self.trail.push_back(self.map.player_pos());
 
         self.animations_step_1 = 7;
         self.animations_step_2 = 0;
         self.timer_end = 6;
+        self.tick = 0;
 
-        self.response = None;
+        self.responses.clear();
         self.reaction = Action::Nothing;
+        self.path = None;
+        self.subscriptions.clear();
+        self.history.clear();
+        self.map_format = MapFormat::Rows;
+        self.framing = Framing::Text;
         self.state = State::PLAYING;
 
         self.player_sprite_direction = Direction::RIGHT;
+        self.camera = Camera::new();
+        self.integer_scaling = settings.integer_scaling;
+        self.terrain_changed();
     }
 
     pub fn tick(&mut self) -> bool {
+        self.tick += 1;
         self.animations_step_1 += 1;
         self.animations_step_1 %= 8;
+        let on_beat = self.animations_step_1.is_multiple_of(4);
+
+        if self.state == State::PLAYING && on_beat {
+            self.emit(GameEvent::Beat(self.tick));
+        }
 
+        if !matches!(self.reaction, Action::Nothing) {
+            self.animations_step_2 += 1;
+            if self.animations_step_2 == 4 {
+                let outcome = self.complete_action();
+                self.reaction = Action::Nothing;
+                self.animations_step_2 = 0;
+                self.report(outcome);
+                // la fin de la partie est annoncée après la réponse au déplacement
+                if outcome == Outcome::Exit {
+                    self.emit(GameEvent::End(self.state.name()));
+                }
+            }
+        }
+
+        // une action qui se termine sur un temps laisse lire la commande suivante dans le même
+        // tick : en rythme strict, le client peut agir à chaque temps
+        let can_read = self.rhythm == Rhythm::Free || on_beat;
         match self.reaction {
-            Action::Nothing => {
-                if self.state != State::PLAYING {
-                    if self.timer_end == 0 {
-                        self.response = Some(ServerMessage::EndConnection);
-                        true
-                    } else {
-                        self.timer_end -= 1;
-                        false
-                    }
+            Action::Nothing if self.state != State::PLAYING => {
+                // les commandes sont encore lues pendant le compte à rebours ; après la
+                // fermeture, seule la fin de la connexion est attendue
+                if self.timer_end == 0 {
+                    self.responses.push_back(ServerMessage::EndConnection);
                 } else {
-                    true
+                    self.timer_end -= 1;
                 }
+                true
             }
+            Action::Nothing => can_read,
+            // pendant une séquence, le client doit pouvoir l'annuler
+            _ => self.path.is_some() && can_read,
+        }
+    }
+
+    fn complete_action(&mut self) -> Outcome {
+        match self.reaction {
             Action::PlayerMovement(direction) => {
-                self.animations_step_2 += 1;
-                if self.animations_step_2 == 4 {
-                    self.map.move_player(direction);
-                    if self.diamonds_left == 0
-                        && self.map.tile_at(self.map.player_pos()) == Some(Tile::EXIT)
-                    {
-                        self.state = State::WON;
+                let origin = self.map.player_pos();
+                self.map.move_player(direction);
+                let destination = self.map.player_pos();
+                let outcome = if self.diamonds_left == 0
+                    && self.map.tile_at(destination) == Some(Tile::EXIT)
+                {
+                    self.set_state(State::WON);
+                    Outcome::Exit
+                } else if self.map.pick_up_diamond() {
+                    Outcome::Diamond
+                } else {
+                    Outcome::Moved
+                };
+                self.diamonds_left = self.map.diamonds_count();
+
+                self.emit(GameEvent::Player(destination));
+                self.trail.push_back(destination);
+                if self.trail.len() > TRAIL_LENGTH {
+                    self.trail.pop_front();
+                }
+                let mut changed = vec![origin, destination];
+                if let Some(fog) = &mut self.fog {
+                    let revealed = fog.reveal(destination);
+                    if !revealed.is_empty() {
+                        changed.extend(revealed);
+                        self.terrain_changed();
+                    }
+                }
+                self.changed(&changed);
+                if outcome == Outcome::Diamond {
+                    self.emit(GameEvent::Diamond(destination, self.diamonds_left));
+                    if self.diamonds_left == 0 {
+                        self.emit(GameEvent::ExitUnlocked);
+                        self.terrain_changed();
                     }
-                    self.map.pick_up_diamond();
-                    self.diamonds_left = self.map.diamonds_count();
-                    self.reaction = Action::Nothing;
-                    self.animations_step_2 = 0;
                 }
-                false
+                outcome
             }
             Action::DigWall(position) => {
-                self.animations_step_2 += 1;
-                if self.animations_step_2 == 4 {
-                    self.map.dig(position);
-                    self.reaction = Action::Nothing;
-                    self.animations_step_2 = 0;
+                self.map.dig(position);
+                self.dug.push(position);
+                self.terrain_changed();
+                self.changed(&[position]);
+                Outcome::Dug
+            }
+            Action::Wait | Action::Nothing => Outcome::Waited,
+        }
+    }
+
+    fn report(&mut self, outcome: Outcome) {
+        match &mut self.path {
+            Some(path) => {
+                path.done += 1;
+                self.responses
+                    .push_back(ServerMessage::StepDone(path.done, outcome));
+                self.next_step();
+            }
+            None => self.responses.push_back(ServerMessage::ActionDone(outcome)),
+        }
+    }
+
+    fn next_step(&mut self) {
+        let step = match &mut self.path {
+            Some(path) if self.state == State::PLAYING => path.steps.pop_front(),
+            _ => None,
+        };
+
+        match step {
+            Some(step) => match self.plan(step) {
+                Ok(action) => self.reaction = action,
+                Err(problem) => {
+                    let done = self.path.take().map_or(0, |path| path.done);
+                    self.responses
+                        .push_back(ServerMessage::StepFailed(done + 1, problem));
+                    self.responses.push_back(ServerMessage::PathDone(done));
+                }
+            },
+            None => {
+                if let Some(path) = self.path.take() {
+                    self.responses.push_back(ServerMessage::PathDone(path.done));
                 }
-                false
             }
         }
     }
 
+    fn plan(&mut self, step: Step) -> Result<Action, Problem> {
+        match step {
+            Step::Move(direction) => {
+                let dest = self.map.player_pos().moved(direction);
+                self.turn_player_sprite(direction);
+                match self.map.tile_at(dest) {
+                    Some(Tile::EMPTY) | Some(Tile::EXIT) => Ok(Action::PlayerMovement(direction)),
+                    // creuser en se déplaçant vers un mur, comme avant l'arrivée de DIG
+                    Some(Tile::WALL) => Ok(Action::DigWall(dest)),
+                    Some(Tile::STONE) | Some(Tile::BORDER) | None => Err(Problem::BlockedMove),
+                }
+            }
+            Step::Dig(direction) => {
+                let dest = self.map.player_pos().moved(direction);
+                self.turn_player_sprite(direction);
+                match self.map.tile_at(dest) {
+                    Some(Tile::WALL) => Ok(Action::DigWall(dest)),
+                    _ => Err(Problem::NothingToDig),
+                }
+            }
+            Step::Wait => Ok(Action::Wait),
+        }
+    }
+
+    fn start(&mut self, step: Step) -> Option<ServerMessage> {
+        if self.path.is_some() {
+            return Some(ServerMessage::Error(Problem::PathInProgress));
+        }
+        match self.plan(step) {
+            Ok(action) => {
+                self.reaction = action;
+                None
+            }
+            Err(problem) => Some(ServerMessage::Error(problem)),
+        }
+    }
+
+    pub fn current_tick(&self) -> u32 {
+        self.tick
+    }
+
     pub fn handle_event(&mut self, ev: Event) {
+        self.camera
+            .handle_event(&ev, self.view(), self.camera_targets().len());
         match ev {
             Event::KeyUp { keycode, .. } => match keycode {
                 Some(Keycode::Escape) => {
-                    if self.state != State::WON {
-                        self.state = State::STOPPED
+                    if self.state == State::PLAYING {
+                        self.set_state(State::STOPPED);
+                        self.emit(GameEvent::End(self.state.name()));
                     }
                 }
+                // affichage seulement : la partie n'est pas modifiée
+                Some(Keycode::M) => self.show_minimap = !self.show_minimap,
+                Some(Keycode::T) => self.show_trail = !self.show_trail,
                 _ => {}
             },
             _ => {}
         }
     }
 
+    // une fois la partie terminée, et jusqu'à la fermeture de la connexion, les commandes qui
+    // ne modifient pas la partie reçoivent encore une réponse
     pub fn react_to_message(&mut self, message: ClientMessage) {
-        if self.state == State::PLAYING {
-            (self.response, self.reaction) = match message {
-                ClientMessage::EmptyCommand => (
-                    Some(ServerMessage::Error("commande vide".to_string())),
-                    Action::Nothing,
-                ),
-
-                ClientMessage::UnknownCommand(command) => (
-                    Some(ServerMessage::Error(format!(
-                        "commande « {} » inconnue",
-                        command
-                    ))),
-                    Action::Nothing,
-                ),
-
-                ClientMessage::InvalidArguments(problem) => {
-                    (Some(ServerMessage::Error(problem)), Action::Nothing)
+        let response = match message {
+            ClientMessage::EndGame
+            | ClientMessage::Move(_)
+            | ClientMessage::Dig(_)
+            | ClientMessage::Wait
+            | ClientMessage::Path(_)
+            | ClientMessage::Cancel
+                if self.state != State::PLAYING =>
+            {
+                Some(ServerMessage::Error(Problem::GameNotRunning))
+            }
+
+            ClientMessage::EmptyCommand => Some(ServerMessage::Error(Problem::EmptyCommand)),
+
+            ClientMessage::UnknownCommand(command) => {
+                Some(ServerMessage::Error(Problem::UnknownCommand(command)))
+            }
+
+            ClientMessage::InvalidArguments(problem) | ClientMessage::ProtocolError(problem) => {
+                Some(ServerMessage::Error(problem))
+            }
+
+            ClientMessage::EndGame => {
+                self.set_state(State::STOPPED);
+                self.responses.push_back(ServerMessage::EndGame {});
+                self.emit(GameEvent::End(self.state.name()));
+                None
+            }
+
+            ClientMessage::GetMap => Some(ServerMessage::MapResponse(self.map_data())),
+
+            ClientMessage::GetMapSince(tick) => match self.history.since(tick) {
+                Some(positions) => Some(ServerMessage::MapDelta(self.tick, self.cells(&positions))),
+                None => Some(ServerMessage::MapSnapshot(self.tick, self.map_data())),
+            },
+
+            ClientMessage::GetStatus => Some(ServerMessage::Status(
+                self.state.name(),
+                self.diamonds_left == 0,
+            )),
+
+            ClientMessage::GetPosition => Some(ServerMessage::Position(self.map.player_pos())),
+
+            ClientMessage::GetDiamonds => Some(ServerMessage::Diamonds(
+                self.map
+                    .diamonds_pos()
+                    .into_iter()
+                    .filter(|pos| self.is_explored(*pos))
+                    .collect(),
+            )),
+
+            ClientMessage::GetTick => Some(ServerMessage::Tick(self.tick)),
+
+            ClientMessage::Help => Some(ServerMessage::Help),
+
+            ClientMessage::Move(direction) => self.start(Step::Move(direction)),
+
+            ClientMessage::Dig(direction) => self.start(Step::Dig(direction)),
+
+            ClientMessage::Wait => self.start(Step::Wait),
+
+            ClientMessage::Path(steps) => {
+                if self.path.is_some() {
+                    Some(ServerMessage::Error(Problem::PathInProgress))
+                } else {
+                    self.responses.push_back(ServerMessage::Queued(steps.len()));
+                    self.path = Some(Path {
+                        steps: steps.into(),
+                        done: 0,
+                    });
+                    self.next_step();
+                    None
                 }
+            }
 
-                ClientMessage::EndGame => {
-                    self.state = State::STOPPED;
-                    (Some(ServerMessage::EndGame {}), Action::Nothing)
+            ClientMessage::Cancel => match &mut self.path {
+                // l'étape en cours se termine normalement, puis DONE est envoyé
+                Some(path) => {
+                    let cancelled = path.steps.len();
+                    path.steps.clear();
+                    Some(ServerMessage::Cancelled(cancelled))
                 }
+                None => Some(ServerMessage::Error(Problem::NoPath)),
+            },
 
-                ClientMessage::GetMap => (
-                    Some(ServerMessage::MapResponse(self.map.repr())),
-                    Action::Nothing,
-                ),
-
-                ClientMessage::Move(direction) => {
-                    let dest = self.map.player_pos().moved(direction);
-                    match direction {
-                        Direction::LEFT => self.player_sprite_direction = Direction::LEFT,
-                        Direction::RIGHT => self.player_sprite_direction = Direction::RIGHT,
-                        _ => {}
+            ClientMessage::Subscribe(kinds) => {
+                for kind in kinds {
+                    if !self.subscriptions.contains(&kind) {
+                        self.subscriptions.push(kind);
                     }
-                    match self.map.tile_at(dest) {
-                        Some(Tile::EMPTY) => (
-                            Some(ServerMessage::ValidMove),
-                            Action::PlayerMovement(direction),
-                        ),
-                        Some(Tile::WALL) => (
-                            Some(ServerMessage::ValidMove),
-                            Action::DigWall(self.map.player_pos().moved(direction)),
-                        ),
+                }
+                Some(ServerMessage::Subscriptions(self.subscriptions.clone()))
+            }
 
-                        Some(Tile::EXIT) => (
-                            Some(ServerMessage::ValidMove),
-                            Action::PlayerMovement(direction),
-                        ),
-                        Some(Tile::STONE) | Some(Tile::BORDER) | None => (
-                            Some(ServerMessage::Error("mouvement invalide".to_string())),
-                            Action::Nothing,
-                        ),
+            ClientMessage::Unsubscribe(kinds) => {
+                self.subscriptions.retain(|kind| !kinds.contains(kind));
+                Some(ServerMessage::Subscriptions(self.subscriptions.clone()))
+            }
+
+            ClientMessage::SetOption(option) => match option {
+                ProtocolOption::Map(MapFormat::Binary) if self.framing != Framing::Binary => {
+                    Some(ServerMessage::Error(Problem::BinaryFramingRequired))
+                }
+                ProtocolOption::Map(format) => {
+                    self.map_format = format;
+                    Some(ServerMessage::OptionSet(option))
+                }
+                ProtocolOption::Framing(framing) => {
+                    self.framing = framing;
+                    if framing == Framing::Text && self.map_format == MapFormat::Binary {
+                        self.map_format = MapFormat::Rows;
                     }
+                    Some(ServerMessage::OptionSet(option))
                 }
-                ClientMessage::ConnectionEnded => (
-                    Some(ServerMessage::Error(
-                        "internal error : match arm should not be reachable".to_string(),
-                    )),
-                    Action::Nothing,
-                ),
-            }
+                // la langue des textes d'erreur et la forme des réponses ne concernent que le
+                // thread réseau
+                ProtocolOption::Lang(_) | ProtocolOption::Replies(_) => {
+                    Some(ServerMessage::OptionSet(option))
+                }
+            },
+
+            ClientMessage::ConnectionEnded => Some(ServerMessage::Error(Problem::Internal)),
+        };
+
+        if let Some(response) = response {
+            self.responses.push_back(response);
         }
     }
 
+    // les réponses aux actions ne sont préparées qu'à la fin de leur animation
     pub fn response(&mut self) -> Option<ServerMessage> {
-        match self.reaction {
-            Action::Nothing => match &mut self.response {
-                None => None,
-                response => std::mem::replace(response, None),
+        self.responses.pop_front()
+    }
+
+    // l'évènement END est émis par l'appelant, après la réponse qui a terminé la partie
+    fn set_state(&mut self, state: State) {
+        debug!(
+            "état de la partie : {} -> {} (tick {})",
+            self.state.name(),
+            state.name(),
+            self.tick
+        );
+        self.state = state;
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        if self.subscriptions.contains(&event.kind()) {
+            self.responses.push_back(ServerMessage::Event(event));
+        }
+    }
+
+    fn terrain_changed(&mut self) {
+        self.terrain_version = self.terrain_version.wrapping_add(1);
+    }
+
+    fn changed(&mut self, positions: &[Pos]) {
+        for position in positions {
+            self.history.record(self.tick, *position);
+        }
+        let cells = self.cells(positions);
+        self.emit(GameEvent::Cells(cells));
+    }
+
+    fn map_data(&self) -> MapData {
+        match self.map_format {
+            MapFormat::Binary => {
+                MapData::Binary(self.map.encode_binary(|pos| self.is_explored(pos)))
+            }
+            format => MapData::Text {
+                repr: match &self.fog {
+                    Some(_) => {
+                        let mut repr = String::new();
+                        for y in 0..self.map.height() {
+                            for x in 0..self.map.width() {
+                                repr.push(self.letter_at(Pos::new(x, y)));
+                            }
+                        }
+                        repr
+                    }
+                    None => self.map.repr(),
+                },
+                width: self.map.width(),
+                height: self.map.height(),
+                rows: format == MapFormat::Rows,
             },
-            _ => None,
+        }
+    }
+
+    fn cells(&self, positions: &[Pos]) -> Vec<(Pos, char)> {
+        positions
+            .iter()
+            .map(|pos| (*pos, self.letter_at(*pos)))
+            .collect()
+    }
+
+    // les cases cachées par le brouillard sont notées ?
+    fn letter_at(&self, position: Pos) -> char {
+        if self.is_explored(position) {
+            self.map.letter_at(position)
+        } else {
+            '?'
+        }
+    }
+
+    fn is_explored(&self, position: Pos) -> bool {
+        self.fog
+            .as_ref()
+            .is_none_or(|fog| fog.is_explored(position))
+    }
+
+    // cibles de la caméra : les diamants découverts, puis la sortie si elle est découverte
+    fn camera_targets(&self) -> Vec<Pos> {
+        let mut targets: Vec<Pos> = self
+            .map
+            .diamonds_pos()
+            .into_iter()
+            .filter(|position| self.is_explored(*position))
+            .collect();
+        if self.is_explored(self.map.exit_pos()) {
+            targets.push(self.map.exit_pos());
+        }
+        targets
+    }
+
+    fn view(&self) -> View {
+        // le centre de l'écran tombe sur le bas du lutin, c'est-à-dire sur la case elle-même
+        let center = |x: f32, y: f32| (x + TILE / 2.0, y + TILE);
+        let player = || {
+            center(
+                self.map.player_pos().x as f32 * TILE
+                    + animation_offset_x(&self.reaction, self.animations_step_2) as f32,
+                self.map.player_pos().y as f32 * TILE
+                    + animation_offset_y(&self.reaction, self.animations_step_2) as f32,
+            )
+        };
+        let (x, y) = match self.camera.mode() {
+            Mode::Follow(Focus::Player) => player(),
+            // une cible disparue (diamant ramassé) rend la caméra au joueur
+            Mode::Follow(Focus::Target(i)) => match self.camera_targets().get(i) {
+                Some(target) => center(target.x as f32 * TILE, target.y as f32 * TILE),
+                None => player(),
+            },
+            Mode::Free { x, y } => (x, y),
+            Mode::Fit => {
+                let width = self.map.width() as f32 * TILE;
+                let height = (self.map.height() + 1) as f32 * TILE;
+                let zoom = (1440.0 / width).min(810.0 / height);
+                return View {
+                    x: width / 2.0,
+                    y: height / 2.0,
+                    zoom: if self.integer_scaling {
+                        snap_zoom(zoom, true)
+                    } else {
+                        zoom
+                    },
+                };
+            }
+        };
+        View {
+            x,
+            y,
+            zoom: if self.integer_scaling {
+                snap_zoom(self.camera.zoom(), false)
+            } else {
+                self.camera.zoom()
+            },
+        }
+    }
+
+    // ticks écoulés pour les animations des lutins, en avance d'un tick sur self.tick :
+    // un multiple de 4 tombe sur un temps, comme animations_step_1
+    fn animation_clock(&self) -> u32 {
+        self.tick.wrapping_sub(1)
+    }
+
+    fn turn_player_sprite(&mut self, direction: Direction) {
+        match direction {
+            Direction::LEFT => self.player_sprite_direction = Direction::LEFT,
+            Direction::RIGHT => self.player_sprite_direction = Direction::RIGHT,
+            _ => {}
         }
     }
 
@@ -211,78 +649,78 @@ impl Game {
         canvas: &mut Canvas<T>,
         images: &Images,
         text_renderer: &TextRenderer<U>,
+        terrain: Option<&mut TerrainCache<U>>,
+        language: Language,
     ) where
         T: RenderTarget,
     {
-        let offset_x = self.map.player_pos().x as i32 * -72 + 684
-            - animation_offset_x(&self.reaction, self.animations_step_2);
-        let offset_y = self.map.player_pos().y as i32 * -72 + 333
-            - animation_offset_y(&self.reaction, self.animations_step_2);
-
-        for y in 0..37 {
-            for x in 0..37 {
-                canvas
-                    .copy(
-                        match self.map.tile_at(Pos::new(x, y)).unwrap() {
-                            Tile::EMPTY => {
-                                if (x + y + (self.animations_step_1 as u32 / 4)) % 2 == 0 {
-                                    images.floor_green()
-                                } else {
-                                    images.floor_purple()
-                                }
-                            }
-                            Tile::WALL => images.wall(),
-                            Tile::STONE => images.stone(),
-                            Tile::BORDER => images.border(),
-                            Tile::EXIT => {
-                                if self.diamonds_left == 0 {
-                                    images.exit()
-                                } else {
-                                    images.exit_locked()
-                                }
-                            }
-                        },
-                        None,
-                        Rect::new(
-                            (x * 72) as i32 + offset_x,
-                            (y * 72) as i32 + offset_y,
-                            72,
-                            144,
+        let view = self.view();
+        let (columns, rows) = view.visible_tiles(self.map.width(), self.map.height());
+        let diamonds: HashSet<Pos> = self.map.diamonds_pos().into_iter().collect();
+        let layer = self.animations_step_1 as usize / 4 % 2;
+        let clock = self.animation_clock();
+        let cached = terrain.and_then(|cache| {
+            cache.layer(
+                canvas,
+                images,
+                &self.map,
+                self.terrain_version,
+                |pos| self.is_explored(pos),
+                layer,
+            )
+        });
+
+        for y in rows {
+            // une ligne du cache ne recouvre que sa propre ligne et celle du dessus, déjà
+            // dessinée : les lutins de la ligne peuvent être dessinés après elle
+            if let Some(texture) = cached {
+                if !columns.is_empty() {
+                    canvas
+                        .copy(
+                            texture,
+                            Rect::new(
+                                (columns.start * terrain::SPRITE_WIDTH) as i32,
+                                (y * terrain::SPRITE_HEIGHT) as i32,
+                                columns.len() as u32 * terrain::SPRITE_WIDTH,
+                                terrain::SPRITE_HEIGHT,
+                            ),
+                            view.row_rect(&columns, y),
+                        )
+                        .unwrap();
+                }
+            }
+
+            for x in columns.clone() {
+                if !self.is_explored(Pos::new(x, y)) {
+                    continue;
+                }
+                if cached.is_none() {
+                    images.draw(
+                        canvas,
+                        terrain::tile_image(
+                            self.map.tile_at(Pos::new(x, y)).unwrap(),
+                            (x + y + layer as u32).is_multiple_of(2),
+                            self.diamonds_left == 0,
                         ),
-                    )
-                    .unwrap();
-                for d in self.map.diamonds_pos() {
-                    if d == (x, y) {
-                        canvas
-                            .copy(
-                                images.diamond(),
-                                None,
-                                Rect::new(
-                                    (x * 72) as i32 + offset_x,
-                                    (y * 72) as i32 + offset_y,
-                                    72,
-                                    144,
-                                ),
-                            )
-                            .unwrap();
-                    }
+                        view.tile_rect(x, y),
+                    );
+                }
+                if diamonds.contains(&Pos::new(x, y)) {
+                    images.draw(
+                        canvas,
+                        images.animation_frame("diamond", clock),
+                        view.tile_rect(x, y),
+                    );
                 }
 
                 match self.reaction {
                     Action::DigWall(position) => {
                         if position == (x, y) {
-                            canvas
-                                .copy(
-                                    images.shovel(),
-                                    None,
-                                    Rect::new(
-                                        (x * 72) as i32 + offset_x,
-                                        (y * 72) as i32 + offset_y,
-                                        72,
-                                        144,
-                                    ),
-                                )
-                                .unwrap();
+                            images.draw(
+                                canvas,
+                                images.animation_frame("dig", self.animations_step_2 as u32),
+                                view.tile_rect(x, y),
+                            );
                         }
                     }
                     _ => {}
@@ -305,54 +743,55 @@ impl Game {
                     }
                     _ => self.map.player_pos() == (x, y),
                 } {
-                    canvas
-                        .copy_ex(
-                            match self.animations_step_1 {
-                                0 | 4 => images.cadence_1(),
-                                1 | 5 => images.cadence_2(),
-                                2 | 6 => images.cadence_3(),
-                                _ => images.cadence_4(),
-                            },
-                            None,
-                            Rect::new(
-                                684,
-                                333 - animation_camera_offset_y(
-                                    &self.reaction,
-                                    self.animations_step_2,
-                                ),
-                                72,
-                                144,
-                            ),
-                            0.0,
-                            None,
-                            self.player_sprite_direction == Direction::LEFT,
-                            false,
-                        )
-                        .unwrap();
+                    images.draw_flipped(
+                        canvas,
+                        images.animation_frame("player", clock),
+                        view.sprite_rect(
+                            self.map.player_pos().x as f32 * TILE
+                                + animation_offset_x(&self.reaction, self.animations_step_2) as f32,
+                            self.map.player_pos().y as f32 * TILE
+                                + animation_offset_y(&self.reaction, self.animations_step_2) as f32
+                                - animation_camera_offset_y(&self.reaction, self.animations_step_2)
+                                    as f32,
+                        ),
+                        self.player_sprite_direction == Direction::LEFT,
+                    );
                 }
             }
         }
 
-        canvas.fill_rect(Rect::new(1356, 63, 216, 33)).unwrap();
-        let diamond_text = text_renderer.render(
-            &format!("x{}", 10 - self.diamonds_left),
-            Blended(Color::RGB(255, 255, 255)),
+        // le contour rend le compteur lisible par-dessus la carte
+        text_renderer.draw(
+            canvas,
+            &format!("x{}", self.diamonds_total - self.diamonds_left),
+            Outlined(Color::RGB(255, 255, 255), Color::RGB(0, 0, 0)),
+            (1386, 64),
+            Anchor::TopLeft,
         );
-        canvas
-            .copy(
-                diamond_text.texture(),
-                None,
-                Rect::new(1386, 66, diamond_text.width(), diamond_text.height()),
-            )
-            .unwrap();
-        canvas
-            .copy(images.diamond_icon(), None, Rect::new(1332, 60, 51, 39))
-            .unwrap();
+        images.draw(canvas, "diamond_icon", Rect::new(1332, 60, 51, 39));
+
+        if self.show_minimap {
+            canvas.fill_rect(Rect::new(1176, 546, 256, 256)).unwrap();
+            minimap::draw(
+                canvas,
+                &self.map,
+                (1184, 554, 240),
+                |pos| self.is_explored(pos),
+                if self.show_trail {
+                    Some(minimap::Overlay {
+                        dug: &self.dug,
+                        trail: &self.trail,
+                    })
+                } else {
+                    None
+                },
+            );
+        }
 
         match self.state {
             State::PLAYING => {}
-            State::STOPPED => draw_message("Partie interrompue", text_renderer, canvas),
-            State::WON => draw_message("Niveau terminé", text_renderer, canvas),
+            State::STOPPED => draw_message(language.text(Text::GameStopped), text_renderer, canvas),
+            State::WON => draw_message(language.text(Text::LevelComplete), text_renderer, canvas),
         }
     }
 }
@@ -361,25 +800,12 @@ fn draw_message<T, U>(msg: &str, tr: &TextRenderer<U>, c: &mut Canvas<T>)
 where
     T: RenderTarget,
 {
-    let message = tr.render(msg, Shaded(Color::RGB(255, 255, 255), Color::RGB(0, 0, 0)));
-    c.fill_rect(Rect::new(
-        690 - message.width() as i32 / 2,
-        390 - message.height() as i32 / 2,
-        message.width() + 60,
-        message.height() + 30,
-    ))
-    .unwrap();
-    c.copy(
-        message.texture(),
-        None,
-        Rect::new(
-            720 - message.width() as i32 / 2,
-            405 - message.height() as i32 / 2,
-            message.width(),
-            message.height(),
-        ),
-    )
-    .unwrap();
+    let style = TextStyle::from(Shaded(Color::RGB(255, 255, 255), Color::RGB(0, 0, 0)))
+        .sized(FontSize::Large);
+    let message = tr.render(msg, style);
+    c.fill_rect(Anchor::Center.place((720, 405), message.width() + 60, message.height() + 30))
+        .unwrap();
+    tr.draw(c, msg, style, (720, 405), Anchor::Center);
 }
 
 fn animation_offset_x(action: &Action, step: u8) -> i32 {
@@ -401,7 +827,7 @@ fn animation_offset_x(action: &Action, step: u8) -> i32 {
                 _ => -72,
             },
         },
-        Action::Nothing | Action::DigWall { .. } => 0,
+        Action::Nothing | Action::DigWall { .. } | Action::Wait => 0,
     }
 }
 
@@ -424,7 +850,7 @@ fn animation_offset_y(action: &Action, step: u8) -> i32 {
                 _ => -72,
             },
         },
-        Action::Nothing | Action::DigWall { .. } => 0,
+        Action::Nothing | Action::DigWall { .. } | Action::Wait => 0,
     }
 }
 
@@ -440,6 +866,6 @@ fn animation_camera_offset_y(action: &Action, step: u8) -> i32 {
             },
             Direction::DOWN | Direction::UP => 0,
         },
-        Action::Nothing | Action::DigWall { .. } => 0,
+        Action::Nothing | Action::DigWall { .. } | Action::Wait => 0,
     }
 }
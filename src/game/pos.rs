@@ -6,7 +6,7 @@ pub enum Direction {
     UP,
 }
 
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 pub struct Pos {
     pub x: u32,
     pub y: u32,
@@ -0,0 +1,159 @@
+use super::map::{Map, Tile};
+use super::pos::Pos;
+use crate::args::option;
+use crate::resource::image::Images;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget, Texture, TextureCreator};
+
+// taille des images des cases, avant agrandissement
+pub const SPRITE_WIDTH: u32 = 24;
+pub const SPRITE_HEIGHT: u32 = 48;
+
+// option reconnue : --terrain-cache on|off (activé par défaut)
+pub fn enabled(args: &[String]) -> bool {
+    match option(args, "--terrain-cache") {
+        Some("off") => false,
+        Some("on") | None => true,
+        Some(value) => {
+            warn!("option « --terrain-cache {} » ignorée", value);
+            true
+        }
+    }
+}
+
+// nom de l'image de la case dans la planche de lutins
+pub fn tile_image(tile: Tile, green_floor: bool, exit_open: bool) -> &'static str {
+    match tile {
+        Tile::EMPTY => {
+            if green_floor {
+                "floor_green"
+            } else {
+                "floor_purple"
+            }
+        }
+        Tile::WALL => "wall",
+        Tile::STONE => "stone",
+        Tile::BORDER => "border",
+        Tile::EXIT => {
+            if exit_open {
+                "exit"
+            } else {
+                "exit_locked"
+            }
+        }
+    }
+}
+
+// le terrain dessiné à sa taille d'origine, une ligne de cases par bande de 48 pixels
+// (les lutins dépassent sur la ligne du dessus, les lignes ne peuvent donc pas se
+// superposer dans la texture) ; une texture par couleur du sol, qui alterne à chaque
+// demi-temps. Les cases non découvertes restent transparentes.
+pub struct TerrainCache<'lt, T> {
+    texture_creator: &'lt TextureCreator<T>,
+    layers: Vec<Texture<'lt>>,
+    size: (u32, u32),
+    version: Option<u32>,
+    disabled: bool,
+}
+
+impl<'lt, T> TerrainCache<'lt, T> {
+    pub fn new(texture_creator: &'lt TextureCreator<T>) -> Self {
+        Self {
+            texture_creator,
+            layers: Vec::new(),
+            size: (0, 0),
+            version: None,
+            disabled: false,
+        }
+    }
+
+    // version : compteur de la partie, modifié à chaque changement du terrain ;
+    // None si le cache ne peut pas être utilisé, le terrain est alors dessiné case par case
+    pub fn layer<C, F>(
+        &mut self,
+        canvas: &mut Canvas<C>,
+        images: &Images,
+        map: &Map,
+        version: u32,
+        explored: F,
+        layer: usize,
+    ) -> Option<&Texture<'lt>>
+    where
+        C: RenderTarget,
+        F: Fn(Pos) -> bool,
+    {
+        if self.disabled {
+            return None;
+        }
+        if self.version != Some(version) {
+            if let Err(err) = self.rebuild(canvas, images, map, &explored) {
+                warn!("cache du terrain désactivé : {}", err);
+                self.disabled = true;
+                self.layers.clear();
+                return None;
+            }
+            self.version = Some(version);
+        }
+        self.layers.get(layer)
+    }
+
+    fn rebuild<C, F>(
+        &mut self,
+        canvas: &mut Canvas<C>,
+        images: &Images,
+        map: &Map,
+        explored: &F,
+    ) -> Result<(), String>
+    where
+        C: RenderTarget,
+        F: Fn(Pos) -> bool,
+    {
+        let size = (map.width() * SPRITE_WIDTH, map.height() * SPRITE_HEIGHT);
+        if size != self.size || self.layers.is_empty() {
+            self.layers.clear();
+            for _ in 0..2 {
+                let mut texture = self
+                    .texture_creator
+                    .create_texture_target(PixelFormatEnum::RGBA8888, size.0, size.1)
+                    .map_err(|err| err.to_string())?;
+                texture.set_blend_mode(BlendMode::Blend);
+                self.layers.push(texture);
+            }
+            self.size = size;
+        }
+
+        let exit_open = map.diamonds_count() == 0;
+        for (layer, texture) in self.layers.iter_mut().enumerate() {
+            canvas
+                .with_texture_canvas(texture, |layer_canvas| {
+                    layer_canvas.set_draw_color(Color::RGBA(0, 0, 0, 0));
+                    layer_canvas.clear();
+                    for y in 0..map.height() {
+                        for x in 0..map.width() {
+                            let position = Pos::new(x, y);
+                            if !explored(position) {
+                                continue;
+                            }
+                            if let Some(tile) = map.tile_at(position) {
+                                let green_floor = (x + y + layer as u32).is_multiple_of(2);
+                                images.draw(
+                                    layer_canvas,
+                                    tile_image(tile, green_floor, exit_open),
+                                    Rect::new(
+                                        (x * SPRITE_WIDTH) as i32,
+                                        (y * SPRITE_HEIGHT) as i32,
+                                        SPRITE_WIDTH,
+                                        SPRITE_HEIGHT,
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                    layer_canvas.set_draw_color(Color::RGB(0, 0, 0));
+                })
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+}
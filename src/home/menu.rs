@@ -0,0 +1,142 @@
+use crate::i18n::{Language, Text};
+use crate::resource::text::TextRenderingFormat::{Blended, Shaded};
+use crate::resource::text::{Anchor, FontSize, TextRenderer, TextStyle};
+use crate::settings::{Setting, Settings, SETTINGS};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget};
+
+// position du panneau et des lignes des paramètres
+const PANEL: (i32, i32, u32, u32) = (270, 100, 900, 610);
+const FIRST_ROW_Y: i32 = 164;
+const ROW_HEIGHT: i32 = 40;
+
+// écran des paramètres, ouvert par-dessus l'écran d'accueil
+pub struct SettingsMenu {
+    selected: usize,
+    before: Settings,
+}
+
+pub enum MenuEvent {
+    Nothing,
+    Close,
+}
+
+impl SettingsMenu {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            selected: 0,
+            before: settings.clone(),
+        }
+    }
+
+    // indique si les paramètres ont changé depuis l'ouverture de l'écran
+    pub fn changed(&self, settings: &Settings) -> bool {
+        self.before != *settings
+    }
+
+    pub fn handle_event(&mut self, ev: Event, settings: &mut Settings) -> MenuEvent {
+        let setting = SETTINGS[self.selected];
+        match ev {
+            Event::KeyDown { keycode, .. } => match keycode {
+                Some(Keycode::Up) => {
+                    self.selected = (self.selected + SETTINGS.len() - 1) % SETTINGS.len()
+                }
+                Some(Keycode::Down) => self.selected = (self.selected + 1) % SETTINGS.len(),
+                Some(Keycode::Left) => settings.change(setting, false),
+                Some(Keycode::Right) => settings.change(setting, true),
+                Some(Keycode::Backspace) if setting == Setting::Port => settings.erase_port_digit(),
+                Some(Keycode::Escape) | Some(Keycode::Return) | Some(Keycode::P) => {
+                    return MenuEvent::Close
+                }
+                _ => {}
+            },
+            Event::TextInput { text, .. } if setting == Setting::Port => {
+                for c in text.chars() {
+                    if let Some(digit) = c.to_digit(10) {
+                        settings.type_port_digit(digit);
+                    }
+                }
+            }
+            // clic gauche : valeur suivante, clic droit : valeur précédente
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                if !Rect::new(PANEL.0, PANEL.1, PANEL.2, PANEL.3).contains_point((x, y)) {
+                    return MenuEvent::Close;
+                }
+                if y >= FIRST_ROW_Y {
+                    let row = ((y - FIRST_ROW_Y) / ROW_HEIGHT) as usize;
+                    if row < SETTINGS.len() {
+                        self.selected = row;
+                        match mouse_btn {
+                            MouseButton::Left => settings.change(SETTINGS[row], true),
+                            MouseButton::Right => settings.change(SETTINGS[row], false),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        MenuEvent::Nothing
+    }
+
+    pub fn draw<T, U>(
+        &self,
+        canvas: &mut Canvas<T>,
+        text_renderer: &TextRenderer<U>,
+        settings: &Settings,
+        language: Language,
+    ) where
+        T: RenderTarget,
+    {
+        let white = Color::RGB(255, 255, 255);
+        let yellow = Color::RGB(255, 220, 0);
+        let black = Color::RGB(0, 0, 0);
+
+        canvas
+            .fill_rect(Rect::new(PANEL.0, PANEL.1, PANEL.2, PANEL.3))
+            .unwrap();
+
+        text_renderer.draw(
+            canvas,
+            language.text(Text::Settings),
+            TextStyle::from(Shaded(white, black)).sized(FontSize::Large),
+            (720, PANEL.1 + 16),
+            Anchor::Top,
+        );
+
+        for (i, setting) in SETTINGS.iter().enumerate() {
+            let color = if i == self.selected { yellow } else { white };
+            let y = FIRST_ROW_Y + i as i32 * ROW_HEIGHT;
+
+            text_renderer.draw(
+                canvas,
+                language.text(Settings::label(*setting)),
+                Blended(color),
+                (PANEL.0 + 50, y),
+                Anchor::TopLeft,
+            );
+            text_renderer.draw(
+                canvas,
+                &format!("< {} >", settings.value(*setting, language)),
+                Blended(color),
+                (PANEL.0 + PANEL.2 as i32 - 50, y),
+                Anchor::TopRight,
+            );
+        }
+
+        text_renderer.draw_wrapped(
+            canvas,
+            language.text(Text::SettingsHelp),
+            TextStyle::from(Shaded(white, black)).sized(FontSize::Small),
+            (720, PANEL.1 + PANEL.3 as i32 - 20),
+            Anchor::Bottom,
+            PANEL.2 - 100,
+        );
+    }
+}
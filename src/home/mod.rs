@@ -1,63 +1,212 @@
+use crate::i18n::{Language, Text};
 use crate::resource::image::Images;
-use crate::resource::text::TextRenderer;
 use crate::resource::text::TextRenderingFormat::Shaded;
+use crate::resource::text::{Anchor, FontSize, TextRenderer, TextStyle};
+use crate::settings::{Setting, Settings};
+use sdl2::clipboard::ClipboardUtil;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
-use sdl2::rect::Rect;
 use sdl2::render::{Canvas, RenderTarget};
-use std::str::FromStr;
+use std::collections::VecDeque;
+
+mod menu;
+mod preview;
+mod seed;
+
+use menu::{MenuEvent, SettingsMenu};
+use preview::Preview;
+
+// nombre de graines gardées dans l'historique
+const HISTORY_LENGTH: usize = 8;
 
 pub struct Home {
-    seed: u32,
+    seed: String,
     editing: bool,
     input_text: String,
+    history: VecDeque<String>,
+    history_index: Option<usize>,
+    clipboard: ClipboardUtil,
+    settings: Settings,
+    menu: Option<SettingsMenu>,
+    settings_changed: bool,
+    preview: Preview,
 }
 
 impl Home {
-    pub fn new() -> Self {
+    pub fn new(settings: Settings, clipboard: ClipboardUtil) -> Self {
         Self {
+            preview: Preview::new(seed::parse("0"), &settings),
             editing: false,
             input_text: "0".to_string(),
-            seed: 0,
+            seed: "0".to_string(),
+            history: VecDeque::new(),
+            history_index: None,
+            clipboard,
+            settings,
+            menu: None,
+            settings_changed: false,
         }
     }
 
     pub fn ready(&self) -> bool {
-        !self.editing
+        !self.editing && self.menu.is_none()
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    // vrai une seule fois après la fermeture de l'écran des paramètres, s'ils ont changé
+    pub fn settings_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.settings_changed, false)
+    }
+
+    // F11 bascule le plein écran depuis n'importe quel écran ; le changement est enregistré
+    // comme s'il avait été fait dans l'écran des paramètres
+    pub fn toggle_fullscreen(&mut self) {
+        self.settings.change(Setting::Fullscreen, true);
+        self.settings_changed = true;
+    }
+
+    pub fn seed(&self) -> u64 {
+        seed::parse(&self.seed)
+    }
+
+    // appelé au début d'une partie, pour pouvoir rejouer la même carte plus tard
+    pub fn seed_played(&mut self) {
+        self.history.retain(|played| *played != self.seed);
+        self.history.push_front(self.seed.clone());
+        self.history.truncate(HISTORY_LENGTH);
+    }
+
+    fn commit_seed(&mut self) {
+        self.editing = false;
+        self.history_index = None;
+        let text = self.input_text.trim();
+        self.seed = if text.is_empty() {
+            "0".to_string()
+        } else {
+            text.to_string()
+        };
+        self.input_text = self.seed.clone();
+    }
+
+    fn type_seed(&mut self, text: &str) {
+        for c in text.chars().filter(|c| seed::is_valid_char(*c)) {
+            if self.input_text.len() < seed::MAX_LENGTH {
+                self.input_text.push(c);
+            }
+        }
+    }
+
+    fn random_seed(&mut self) {
+        self.input_text = seed::random();
+        self.commit_seed();
     }
 
-    pub fn seed(&self) -> u32 {
-        self.seed
+    // HAUT remonte vers les graines les plus anciennes, BAS revient vers les plus récentes
+    fn browse_history(&mut self, older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) => 0,
+            (Some(index), true) => index + 1,
+            (Some(index), false) if index > 0 => index - 1,
+            _ => return,
+        };
+        if let Some(played) = self.history.get(index) {
+            self.history_index = Some(index);
+            self.input_text = played.clone();
+        }
     }
 
     pub fn handle_event(&mut self, ev: Event) {
+        if let Some(menu) = &mut self.menu {
+            if let MenuEvent::Close = menu.handle_event(ev, &mut self.settings) {
+                self.settings_changed = menu.changed(&self.settings);
+                self.menu = None;
+            }
+        } else {
+            self.handle_home_event(ev);
+        }
+        self.refresh_preview();
+    }
+
+    // pendant la saisie, l'aperçu suit la graine en cours d'écriture
+    fn refresh_preview(&mut self) {
+        let seed = if self.editing {
+            match self.input_text.trim() {
+                "" => seed::parse("0"),
+                text => seed::parse(text),
+            }
+        } else {
+            self.seed()
+        };
+        if !self.preview.matches(seed, &self.settings) {
+            self.preview = Preview::new(seed, &self.settings);
+        }
+    }
+
+    fn handle_home_event(&mut self, ev: Event) {
         match ev {
             Event::MouseButtonUp {
                 mouse_btn, x, y, ..
             } => {
-                if mouse_btn == MouseButton::Left && is_inside((x, y), (0, 730, 720, 810)) {
+                if mouse_btn == MouseButton::Left && is_inside((x, y), (0, 730, 560, 810)) {
                     self.editing = true;
+                } else if mouse_btn == MouseButton::Left
+                    && !self.editing
+                    && is_inside((x, y), (580, 730, 860, 810))
+                {
+                    self.random_seed();
+                } else if mouse_btn == MouseButton::Left
+                    && !self.editing
+                    && is_inside((x, y), (1040, 0, 1440, 60))
+                {
+                    self.menu = Some(SettingsMenu::new(&self.settings));
                 }
             }
             Event::TextInput { text, .. } => {
-                if self.editing && filter(&text) {
-                    self.input_text.push_str(&text)
+                if self.editing {
+                    self.type_seed(&text)
                 }
             }
-            Event::KeyDown { keycode, .. } => match keycode {
-                Some(Keycode::Return) => {
-                    self.editing = false;
-                    match u32::from_str(&self.input_text) {
-                        Ok(seed) => self.seed = seed,
-                        Err(_) => self.seed = 0,
+            Event::KeyDown {
+                keycode, keymod, ..
+            } => match keycode {
+                Some(Keycode::C) if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                    let text = if self.editing {
+                        &self.input_text
+                    } else {
+                        &self.seed
+                    };
+                    if let Err(err) = self.clipboard.set_clipboard_text(text) {
+                        warn!("impossible de copier la graine : {}", err);
                     }
-                    self.input_text = self.seed.to_string();
                 }
-                Some(Keycode::Backspace) => {
+                // coller une graine commence sa saisie si elle n'était pas commencée
+                Some(Keycode::V) if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                    match self.clipboard.clipboard_text() {
+                        Ok(text) => {
+                            if !self.editing {
+                                self.editing = true;
+                                self.input_text.clear();
+                            }
+                            self.type_seed(&text);
+                        }
+                        Err(err) => warn!("impossible de coller la graine : {}", err),
+                    }
+                }
+                Some(Keycode::Return) if self.editing => self.commit_seed(),
+                Some(Keycode::Backspace) if self.editing => {
                     self.input_text.pop();
                 }
+                Some(Keycode::Up) if self.editing => self.browse_history(true),
+                Some(Keycode::Down) if self.editing => self.browse_history(false),
+                Some(Keycode::P) if !self.editing => {
+                    self.menu = Some(SettingsMenu::new(&self.settings));
+                }
+                Some(Keycode::R) if !self.editing => self.random_seed(),
                 _ => {}
             },
             _ => {}
@@ -69,59 +218,75 @@ impl Home {
         canvas: &mut Canvas<T>,
         images: &Images,
         text_renderer: &TextRenderer<U>,
+        language: Language,
     ) where
         T: RenderTarget,
     {
-        canvas.copy(images.background(), None, None).unwrap();
+        images.draw(canvas, "background", None);
+        let style = Shaded(Color::RGB(255, 255, 255), Color::RGB(0, 0, 0));
 
-        let seed_text = text_renderer.render(
+        text_renderer.draw(
+            canvas,
+            language.text(Text::RandomSeed),
+            style,
+            (720, 770),
+            Anchor::Top,
+        );
+
+        if self.editing && !self.history.is_empty() {
+            let played: Vec<&str> = self.history.iter().map(|s| s.as_str()).collect();
+            text_renderer.draw(
+                canvas,
+                &format!(
+                    "{} : {}",
+                    language.text(Text::RecentSeeds),
+                    played.join("  ")
+                ),
+                TextStyle::from(style).sized(FontSize::Small),
+                (16, 760),
+                Anchor::BottomLeft,
+            );
+        }
+
+        text_renderer.draw(
+            canvas,
             &format!(
-                "Seed: {}{}",
+                "{}: {}{}",
+                language.text(Text::Seed),
                 self.input_text,
                 if self.editing { "_" } else { "" }
             ),
-            Shaded(Color::RGB(255, 255, 255), Color::RGB(0, 0, 0)),
+            style,
+            (16, 770),
+            Anchor::TopLeft,
+        );
+        text_renderer.draw(
+            canvas,
+            language.text(Text::OpenSettings),
+            style,
+            (1424, 16),
+            Anchor::TopRight,
         );
-        let status_text = text_renderer.render(
-            if self.editing {
-                "Appuyez sur [ENTRÉE] pour valider"
+        text_renderer.draw(
+            canvas,
+            language.text(if self.editing {
+                Text::PressEnter
             } else {
-                "En attente du client..."
-            },
-            Shaded(Color::RGB(255, 255, 255), Color::RGB(0, 0, 0)),
+                Text::WaitingForClient
+            }),
+            style,
+            (1424, 770),
+            Anchor::TopRight,
         );
 
-        canvas
-            .copy(
-                seed_text.texture(),
-                None,
-                Rect::new(16, 770, seed_text.width(), seed_text.height()),
-            )
-            .unwrap();
-        canvas
-            .copy(
-                status_text.texture(),
-                None,
-                Rect::new(
-                    1424 - status_text.width() as i32,
-                    770,
-                    status_text.width(),
-                    status_text.height(),
-                ),
-            )
-            .unwrap();
+        self.preview.draw(canvas, text_renderer, language);
+
+        if let Some(menu) = &self.menu {
+            menu.draw(canvas, text_renderer, &self.settings, language);
+        }
     }
 }
 
 fn is_inside(point: (i32, i32), bounds: (i32, i32, i32, i32)) -> bool {
     bounds.0 <= point.0 && point.0 <= bounds.2 && bounds.1 <= point.1 && point.1 <= bounds.3
 }
-
-fn filter(string: &str) -> bool {
-    for c in string.chars() {
-        if !c.is_numeric() {
-            return false;
-        }
-    }
-    return true;
-}
@@ -0,0 +1,67 @@
+use crate::game::map::Map;
+use crate::game::minimap;
+use crate::i18n::{Language, Text};
+use crate::resource::text::TextRenderingFormat::Shaded;
+use crate::resource::text::{Anchor, TextRenderer};
+use crate::settings::{Difficulty, Generator, Settings};
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+
+// zone de l'écran d'accueil réservée à l'aperçu
+const AREA: (i32, i32, u32) = (1064, 64, 360);
+
+// aperçu de la carte qui sera jouée avec la graine et les paramètres actuels
+pub struct Preview {
+    key: (u64, u32, Generator, Difficulty),
+    map: Map,
+}
+
+impl Preview {
+    pub fn new(seed: u64, settings: &Settings) -> Self {
+        Self {
+            key: key(seed, settings),
+            map: Map::generate(
+                seed,
+                settings.map_size,
+                settings.generator,
+                settings.difficulty,
+            ),
+        }
+    }
+
+    pub fn matches(&self, seed: u64, settings: &Settings) -> bool {
+        self.key == key(seed, settings)
+    }
+
+    pub fn draw<T, U>(
+        &self,
+        canvas: &mut Canvas<T>,
+        text_renderer: &TextRenderer<U>,
+        language: Language,
+    ) where
+        T: RenderTarget,
+    {
+        minimap::draw(canvas, &self.map, AREA, |_| true, None);
+
+        text_renderer.draw(
+            canvas,
+            &format!(
+                "{} : {}",
+                language.text(Text::Diamonds),
+                self.map.diamonds_count()
+            ),
+            Shaded(Color::RGB(255, 255, 255), Color::RGB(0, 0, 0)),
+            (AREA.0 + AREA.2 as i32 / 2, AREA.1 + AREA.2 as i32 + 8),
+            Anchor::Top,
+        );
+    }
+}
+
+fn key(seed: u64, settings: &Settings) -> (u64, u32, Generator, Difficulty) {
+    (
+        seed,
+        settings.map_size,
+        settings.generator,
+        settings.difficulty,
+    )
+}
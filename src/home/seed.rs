@@ -0,0 +1,77 @@
+use oorandom::Rand32;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// longueur maximale d'une graine saisie ou collée
+pub const MAX_LENGTH: usize = 32;
+
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+// une graine composée uniquement de chiffres est utilisée telle quelle, pour garder les cartes
+// des versions précédentes ; les autres sont hachées avec FNV-1a, qui ne changera pas d'une
+// version de Rust à l'autre contrairement au hachage de la bibliothèque standard
+pub fn parse(text: &str) -> u64 {
+    match u64::from_str(text) {
+        Ok(seed) => seed,
+        Err(_) => {
+            let mut hash: u64 = 0xcbf29ce484222325;
+            for byte in text.bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+            hash
+        }
+    }
+}
+
+pub fn is_valid_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ' '
+}
+
+// graine de 8 caractères, assez courte pour être recopiée à la main
+pub fn random() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut rng = Rand32::new(now.as_nanos() as u64);
+    (0..8)
+        .map(|_| ALPHABET[rng.rand_range(0..ALPHABET.len() as u32) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_seeds_are_used_as_is() {
+        assert_eq!(parse("0"), 0);
+        assert_eq!(parse("42"), 42);
+        assert_eq!(parse("007"), 7);
+        assert_eq!(parse("18446744073709551615"), u64::MAX);
+    }
+
+    #[test]
+    fn other_seeds_are_hashed_with_fnv1a() {
+        // valeurs de référence de FNV-1a 64 bits : elles ne doivent jamais changer
+        assert_eq!(parse(""), 0xcbf29ce484222325);
+        assert_eq!(parse("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(parse("foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn almost_numeric_seeds_are_hashed() {
+        // trop grande pour un u64, négative ou avec des espaces : ce n'est plus un nombre
+        assert_eq!(parse("18446744073709551616"), 0xedf2aa6b38fc416d);
+        assert_eq!(parse("-1"), 0x07d00b07b497d12b);
+        assert_eq!(parse(" 42"), 0xc36ccc17ce78e7d9);
+    }
+
+    #[test]
+    fn random_seeds_can_be_typed() {
+        let seed = random();
+        assert_eq!(seed.len(), 8);
+        assert!(seed.len() <= MAX_LENGTH);
+        assert!(seed.chars().all(is_valid_char));
+    }
+}
@@ -0,0 +1,72 @@
+use super::Text;
+use crate::interface::Problem;
+
+pub fn text(text: Text) -> &'static str {
+    match text {
+        Text::Loading => "Loading ...",
+        Text::GameStopped => "Game interrupted",
+        Text::LevelComplete => "Level complete",
+        Text::Seed => "Seed",
+        Text::PressEnter => "Press [ENTER] to confirm",
+        Text::WaitingForClient => "Waiting for the client...",
+        Text::RandomSeed => "[R] Random seed",
+        Text::RecentSeeds => "Recent ([UP]/[DOWN])",
+        Text::Diamonds => "Diamonds",
+        Text::OpenSettings => "[P] Settings",
+        Text::Settings => "Settings",
+        Text::SettingsHelp => "[UP]/[DOWN] select   [LEFT]/[RIGHT] change   [ESC] close",
+        Text::Bpm => "Tempo (BPM)",
+        Text::MapSize => "Map size",
+        Text::Generator => "Generator",
+        Text::Difficulty => "Difficulty",
+        Text::Fog => "Fog of war",
+        Text::Rhythm => "Rhythm",
+        Text::Port => "Listen port",
+        Text::Language => "Language",
+        Text::Volume => "Volume",
+        Text::Fullscreen => "Fullscreen",
+        Text::IntegerScaling => "Integer scaling",
+        Text::On => "on",
+        Text::Off => "off",
+        Text::Rooms => "rooms",
+        Text::Caves => "caves",
+        Text::Easy => "easy",
+        Text::Normal => "normal",
+        Text::Hard => "hard",
+        Text::Free => "free",
+        Text::Strict => "on the beat",
+    }
+}
+
+pub fn problem(problem: &Problem) -> String {
+    match problem {
+        Problem::EmptyCommand => "empty command".to_string(),
+        Problem::UnknownCommand(command) => format!("unknown command \"{}\"", command),
+        Problem::MissingArguments(command) => {
+            format!("missing arguments for command \"{}\"", command)
+        }
+        Problem::TooManyArguments(command) => {
+            format!("too many arguments for command \"{}\"", command)
+        }
+        Problem::InvalidDirection(direction) => format!("invalid direction \"{}\"", direction),
+        Problem::InvalidTick(tick) => format!("invalid tick \"{}\"", tick),
+        Problem::InvalidStep(step) => format!("invalid step \"{}\"", step),
+        Problem::InvalidOption(name, value) => format!("invalid option \"{} {}\"", name, value),
+        Problem::UnknownEvent(event) => format!("unknown event \"{}\"", event),
+        Problem::InvalidUtf8 => "invalid message (UTF-8 expected)".to_string(),
+        Problem::MessageTooLong => "message too long".to_string(),
+        Problem::BlockedMove => "move blocked by a stone or the border".to_string(),
+        Problem::NothingToDig => "nothing to dig".to_string(),
+        Problem::PathInProgress => "a path is in progress".to_string(),
+        Problem::NoPath => "no path in progress".to_string(),
+        Problem::BinaryFramingRequired => "binary maps require binary framing".to_string(),
+        Problem::GameNotRunning => "the game is not running".to_string(),
+        Problem::TooManyCommands(limit) => {
+            format!("too many commands (at most {} per beat)", limit)
+        }
+        Problem::TooManyRefused => "too many refused commands".to_string(),
+        Problem::GameTimeout => "maximum game duration exceeded".to_string(),
+        Problem::IdleTimeout => "idle timeout exceeded".to_string(),
+        Problem::Internal => "internal error".to_string(),
+    }
+}
@@ -0,0 +1,74 @@
+use super::Text;
+use crate::interface::Problem;
+
+pub fn text(text: Text) -> &'static str {
+    match text {
+        Text::Loading => "Chargement ...",
+        Text::GameStopped => "Partie interrompue",
+        Text::LevelComplete => "Niveau terminé",
+        Text::Seed => "Seed",
+        Text::PressEnter => "Appuyez sur [ENTRÉE] pour valider",
+        Text::WaitingForClient => "En attente du client...",
+        Text::RandomSeed => "[R] Graine aléatoire",
+        Text::RecentSeeds => "Récentes ([HAUT]/[BAS])",
+        Text::Diamonds => "Diamants",
+        Text::OpenSettings => "[P] Paramètres",
+        Text::Settings => "Paramètres",
+        Text::SettingsHelp => "[HAUT]/[BAS] choisir   [GAUCHE]/[DROITE] modifier   [ÉCHAP] fermer",
+        Text::Bpm => "Tempo (BPM)",
+        Text::MapSize => "Taille de la carte",
+        Text::Generator => "Générateur",
+        Text::Difficulty => "Difficulté",
+        Text::Fog => "Brouillard de guerre",
+        Text::Rhythm => "Rythme",
+        Text::Port => "Port d'écoute",
+        Text::Language => "Langue",
+        Text::Volume => "Volume",
+        Text::Fullscreen => "Plein écran",
+        Text::IntegerScaling => "Agrandissement entier",
+        Text::On => "activé",
+        Text::Off => "désactivé",
+        Text::Rooms => "salles",
+        Text::Caves => "cavernes",
+        Text::Easy => "facile",
+        Text::Normal => "normale",
+        Text::Hard => "difficile",
+        Text::Free => "libre",
+        Text::Strict => "sur les temps",
+    }
+}
+
+pub fn problem(problem: &Problem) -> String {
+    match problem {
+        Problem::EmptyCommand => "commande vide".to_string(),
+        Problem::UnknownCommand(command) => format!("commande « {} » inconnue", command),
+        Problem::MissingArguments(command) => {
+            format!("arguments manquants pour la commande « {} »", command)
+        }
+        Problem::TooManyArguments(command) => {
+            format!("trop d'arguments pour la commande « {} »", command)
+        }
+        Problem::InvalidDirection(direction) => format!("direction « {} » invalide", direction),
+        Problem::InvalidTick(tick) => format!("tick « {} » invalide", tick),
+        Problem::InvalidStep(step) => format!("étape « {} » invalide", step),
+        Problem::InvalidOption(name, value) => format!("option « {} {} » invalide", name, value),
+        Problem::UnknownEvent(event) => format!("évènement « {} » inconnu", event),
+        Problem::InvalidUtf8 => "message invalide (UTF-8 attendu)".to_string(),
+        Problem::MessageTooLong => "message trop long".to_string(),
+        Problem::BlockedMove => "déplacement bloqué par une pierre ou une bordure".to_string(),
+        Problem::NothingToDig => "rien à creuser".to_string(),
+        Problem::PathInProgress => "une séquence est en cours".to_string(),
+        Problem::NoPath => "aucune séquence en cours".to_string(),
+        Problem::BinaryFramingRequired => {
+            "les cartes binaires nécessitent des trames binaires".to_string()
+        }
+        Problem::GameNotRunning => "la partie n'est pas en cours".to_string(),
+        Problem::TooManyCommands(limit) => {
+            format!("trop de commandes (au plus {} par temps)", limit)
+        }
+        Problem::TooManyRefused => "trop de commandes refusées".to_string(),
+        Problem::GameTimeout => "durée maximale de la partie dépassée".to_string(),
+        Problem::IdleTimeout => "délai d'inactivité dépassé".to_string(),
+        Problem::Internal => "erreur interne".to_string(),
+    }
+}
@@ -0,0 +1,91 @@
+use crate::args::option;
+use crate::interface::Problem;
+
+mod en;
+mod fr;
+
+// langue de l'interface et des textes d'erreur envoyés aux clients ;
+// les codes d'erreur du protocole, eux, ne dépendent pas de la langue
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Language {
+    French,
+    English,
+}
+
+// textes affichés par l'interface
+#[derive(Copy, Clone)]
+pub enum Text {
+    Loading,
+    GameStopped,
+    LevelComplete,
+    Seed,
+    PressEnter,
+    WaitingForClient,
+    RandomSeed,
+    RecentSeeds,
+    Diamonds,
+    OpenSettings,
+    Settings,
+    SettingsHelp,
+    Bpm,
+    MapSize,
+    Generator,
+    Difficulty,
+    Fog,
+    Rhythm,
+    Port,
+    Language,
+    Volume,
+    Fullscreen,
+    IntegerScaling,
+    On,
+    Off,
+    Rooms,
+    Caves,
+    Easy,
+    Normal,
+    Hard,
+    Free,
+    Strict,
+}
+
+impl Language {
+    pub fn parse(code: &str) -> Option<Self> {
+        match code.to_ascii_uppercase().as_str() {
+            "FR" => Some(Language::French),
+            "EN" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::French => "FR",
+            Language::English => "EN",
+        }
+    }
+
+    // option reconnue : --lang <fr|en>, prioritaire sur le fichier de configuration
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let code = option(args, "--lang")?;
+        let language = Language::parse(code);
+        if language.is_none() {
+            warn!("option « --lang {} » ignorée", code);
+        }
+        language
+    }
+
+    pub fn text(&self, text: Text) -> &'static str {
+        match self {
+            Language::French => fr::text(text),
+            Language::English => en::text(text),
+        }
+    }
+
+    pub fn problem(&self, problem: &Problem) -> String {
+        match self {
+            Language::French => fr::problem(problem),
+            Language::English => en::problem(problem),
+        }
+    }
+}
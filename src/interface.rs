@@ -1,64 +1,411 @@
-use crate::game::pos::Direction;
+use crate::framing::Encoding;
+use crate::game::pos::{Direction, Pos};
+use crate::i18n::Language;
+use std::str::FromStr;
 
 #[derive(Debug)]
 pub enum ClientMessage {
     ConnectionEnded,
     EmptyCommand,
     UnknownCommand(String),
-    InvalidArguments(String),
+    InvalidArguments(Problem),
+    ProtocolError(Problem),
     EndGame,
     GetMap,
+    GetMapSince(u32),
     Move(Direction),
+    Dig(Direction),
+    Wait,
+    Path(Vec<Step>),
+    Cancel,
+    GetStatus,
+    GetPosition,
+    GetDiamonds,
+    GetTick,
+    Help,
+    Subscribe(Vec<EventKind>),
+    Unsubscribe(Vec<EventKind>),
+    SetOption(ProtocolOption),
+}
+
+// options négociées par le client avec la commande OPTION
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProtocolOption {
+    Map(MapFormat),
+    Framing(Framing),
+    Lang(Option<Language>),
+    Replies(Replies),
+}
+
+impl ProtocolOption {
+    fn parse(name: &str, value: &str) -> Option<Self> {
+        match (name, value) {
+            ("MAP", "ROWS") => Some(Self::Map(MapFormat::Rows)),
+            ("MAP", "LINE") => Some(Self::Map(MapFormat::Line)),
+            ("MAP", "BINARY") => Some(Self::Map(MapFormat::Binary)),
+            ("FRAMING", "TEXT") => Some(Self::Framing(Framing::Text)),
+            ("FRAMING", "BINARY") => Some(Self::Framing(Framing::Binary)),
+            ("LANG", "NONE") => Some(Self::Lang(None)),
+            ("LANG", code) => Language::parse(code).map(|language| Self::Lang(Some(language))),
+            ("REPLIES", "PLAIN") => Some(Self::Replies(Replies::Plain)),
+            ("REPLIES", "DETAILED") => Some(Self::Replies(Replies::Detailed)),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Map(_) => "MAP",
+            Self::Framing(_) => "FRAMING",
+            Self::Lang(_) => "LANG",
+            Self::Replies(_) => "REPLIES",
+        }
+    }
+
+    fn value(&self) -> &'static str {
+        match self {
+            Self::Map(MapFormat::Rows) => "ROWS",
+            Self::Map(MapFormat::Line) => "LINE",
+            Self::Map(MapFormat::Binary) | Self::Framing(Framing::Binary) => "BINARY",
+            Self::Framing(Framing::Text) => "TEXT",
+            Self::Lang(Some(language)) => language.code(),
+            Self::Lang(None) => "NONE",
+            Self::Replies(Replies::Plain) => "PLAIN",
+            Self::Replies(Replies::Detailed) => "DETAILED",
+        }
+    }
+}
+
+// ROWS : un en-tête « MAP <largeur> <hauteur> <lignes> », une ligne par rangée, puis ENDMAP
+// LINE : toute la carte sur une seule ligne, comme dans les premières versions
+// BINARY : cases et entités encodées en binaire (voir PROTOCOLE.md), en trames binaires seulement
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MapFormat {
+    Rows,
+    Line,
+    Binary,
+}
+
+// TEXT : un message par ligne
+// BINARY : chaque message est précédé de sa taille (voir PROTOCOLE.md)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Framing {
+    Text,
+    Binary,
+}
+
+// PLAIN : les actions terminées répondent OK, comme avant l'arrivée de DIG et WAIT
+// DETAILED : OK suivi du résultat de l'action (MOVED, DUG, DIAMOND, EXIT ou WAITED)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Replies {
+    Plain,
+    Detailed,
+}
+
+#[derive(Debug)]
+pub enum MapData {
+    Text {
+        repr: String,
+        width: u32,
+        height: u32,
+        rows: bool,
+    },
+    Binary(Vec<u8>),
+}
+
+impl MapData {
+    fn encode(self) -> Vec<u8> {
+        match self {
+            Self::Text {
+                repr, rows: false, ..
+            } => repr.into_bytes(),
+            Self::Text {
+                repr,
+                width,
+                height,
+                rows: true,
+            } => {
+                let mut text = format!("MAP {} {} {}", width, height, height);
+                for y in 0..height as usize {
+                    let start = y * width as usize;
+                    text.push('\n');
+                    text.push_str(&repr[start..start + width as usize]);
+                }
+                text.push_str("\nENDMAP");
+                text.into_bytes()
+            }
+            Self::Binary(data) => data,
+        }
+    }
+}
+
+// une étape d'une séquence envoyée avec PATH
+#[derive(Debug, Copy, Clone)]
+pub enum Step {
+    Move(Direction),
+    Dig(Direction),
+    Wait,
+}
+
+// codes d'erreur envoyés aux clients ; ils sont stables d'une version à l'autre : on peut en
+// ajouter, mais pas en renommer ni en retirer (voir PROTOCOLE.md)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ErrorCode {
+    EmptyCommand,
+    UnknownCommand,
+    ArgumentCount,
+    InvalidArgument,
+    InvalidDirection,
+    InvalidEncoding,
+    MessageTooLong,
+    BlockedMove,
+    NothingToDig,
+    PathInProgress,
+    NoPath,
+    OptionConflict,
+    GameNotRunning,
+    RateLimited,
+    IdleTimeout,
+    GameTimeout,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorCode::EmptyCommand => "EMPTY_COMMAND",
+            ErrorCode::UnknownCommand => "UNKNOWN_COMMAND",
+            ErrorCode::ArgumentCount => "ARGUMENT_COUNT",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::InvalidDirection => "INVALID_DIRECTION",
+            ErrorCode::InvalidEncoding => "INVALID_ENCODING",
+            ErrorCode::MessageTooLong => "MESSAGE_TOO_LONG",
+            ErrorCode::BlockedMove => "BLOCKED_MOVE",
+            ErrorCode::NothingToDig => "NOTHING_TO_DIG",
+            ErrorCode::PathInProgress => "PATH_IN_PROGRESS",
+            ErrorCode::NoPath => "NO_PATH",
+            ErrorCode::OptionConflict => "OPTION_CONFLICT",
+            ErrorCode::GameNotRunning => "GAME_NOT_RUNNING",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::IdleTimeout => "IDLE_TIMEOUT",
+            ErrorCode::GameTimeout => "GAME_TIMEOUT",
+            ErrorCode::Internal => "INTERNAL_ERROR",
+        }
+    }
+}
+
+// erreurs envoyées aux clients sous la forme « NOK <code> <texte> » : plusieurs problèmes
+// peuvent partager un code, le texte est traduit (voir i18n) et omis avec OPTION LANG NONE
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    EmptyCommand,
+    UnknownCommand(String),
+    MissingArguments(String),
+    TooManyArguments(String),
+    InvalidDirection(String),
+    InvalidTick(String),
+    InvalidStep(String),
+    InvalidOption(String, String),
+    UnknownEvent(String),
+    InvalidUtf8,
+    MessageTooLong,
+    BlockedMove,
+    NothingToDig,
+    PathInProgress,
+    NoPath,
+    BinaryFramingRequired,
+    GameNotRunning,
+    TooManyCommands(u32),
+    TooManyRefused,
+    GameTimeout,
+    IdleTimeout,
+    Internal,
+}
+
+impl Problem {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Problem::EmptyCommand => ErrorCode::EmptyCommand,
+            Problem::UnknownCommand(_) => ErrorCode::UnknownCommand,
+            Problem::MissingArguments(_) | Problem::TooManyArguments(_) => ErrorCode::ArgumentCount,
+            Problem::InvalidDirection(_) => ErrorCode::InvalidDirection,
+            Problem::InvalidTick(_)
+            | Problem::InvalidStep(_)
+            | Problem::InvalidOption(_, _)
+            | Problem::UnknownEvent(_) => ErrorCode::InvalidArgument,
+            Problem::InvalidUtf8 => ErrorCode::InvalidEncoding,
+            Problem::MessageTooLong => ErrorCode::MessageTooLong,
+            Problem::BlockedMove => ErrorCode::BlockedMove,
+            Problem::NothingToDig => ErrorCode::NothingToDig,
+            Problem::PathInProgress => ErrorCode::PathInProgress,
+            Problem::NoPath => ErrorCode::NoPath,
+            Problem::BinaryFramingRequired => ErrorCode::OptionConflict,
+            Problem::GameNotRunning => ErrorCode::GameNotRunning,
+            Problem::TooManyCommands(_) | Problem::TooManyRefused => ErrorCode::RateLimited,
+            Problem::GameTimeout => ErrorCode::GameTimeout,
+            Problem::IdleTimeout => ErrorCode::IdleTimeout,
+            Problem::Internal => ErrorCode::Internal,
+        }
+    }
+
+    fn encode(&self, language: Option<Language>) -> String {
+        match language {
+            Some(language) => format!("{} {}", self.code().name(), language.problem(self)),
+            None => self.code().name().to_string(),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum ServerMessage {
     EndConnection,
-    Error(String),
-    ValidMove,
-    MapResponse(String),
+    Error(Problem),
+    ActionDone(Outcome),
+    MapResponse(MapData),
+    MapDelta(u32, Vec<(Pos, char)>),
+    MapSnapshot(u32, MapData),
     EndGame,
+    Status(&'static str, bool),
+    Position(Pos),
+    Diamonds(Vec<Pos>),
+    Tick(u32),
+    Help,
+    Queued(usize),
+    StepDone(usize, Outcome),
+    StepFailed(usize, Problem),
+    Cancelled(usize),
+    PathDone(usize),
+    Subscriptions(Vec<EventKind>),
+    OptionSet(ProtocolOption),
+    Bye(Problem),
+    Event(GameEvent),
 }
 
+// évènements envoyés aux clients abonnés, sans commande de leur part
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EventKind {
+    Beat,
+    Diamond,
+    Exit,
+    Player,
+    Cells,
+    End,
+}
+
+const EVENT_KINDS: [EventKind; 6] = [
+    EventKind::Beat,
+    EventKind::Diamond,
+    EventKind::Exit,
+    EventKind::Player,
+    EventKind::Cells,
+    EventKind::End,
+];
+
+impl EventKind {
+    fn name(&self) -> &'static str {
+        match self {
+            EventKind::Beat => "BEAT",
+            EventKind::Diamond => "DIAMOND",
+            EventKind::Exit => "EXIT",
+            EventKind::Player => "PLAYER",
+            EventKind::Cells => "CELLS",
+            EventKind::End => "END",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GameEvent {
+    Beat(u32),
+    Diamond(Pos, usize),
+    ExitUnlocked,
+    Player(Pos),
+    Cells(Vec<(Pos, char)>),
+    End(&'static str),
+}
+
+impl GameEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            GameEvent::Beat(_) => EventKind::Beat,
+            GameEvent::Diamond(_, _) => EventKind::Diamond,
+            GameEvent::ExitUnlocked => EventKind::Exit,
+            GameEvent::Player(_) => EventKind::Player,
+            GameEvent::Cells(_) => EventKind::Cells,
+            GameEvent::End(_) => EventKind::End,
+        }
+    }
+}
+
+// nombre maximal d'étapes dans une même séquence
+const MAX_PATH_LENGTH: usize = 256;
+
+// résultat d'une action une fois son animation terminée
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Outcome {
+    Moved,
+    Dug,
+    Diamond,
+    Exit,
+    Waited,
+}
+
+impl Outcome {
+    fn name(&self) -> &'static str {
+        match self {
+            Outcome::Moved => "MOVED",
+            Outcome::Dug => "DUG",
+            Outcome::Diamond => "DIAMOND",
+            Outcome::Exit => "EXIT",
+            Outcome::Waited => "WAITED",
+        }
+    }
+}
+
+// commandes reconnues, dans l'ordre de la réponse à HELP
+const COMMANDS: [&str; 15] = [
+    "END",
+    "MAP",
+    "MOVE",
+    "DIG",
+    "WAIT",
+    "PATH",
+    "CANCEL",
+    "STATUS",
+    "POS",
+    "DIAMONDS",
+    "TICK",
+    "SUBSCRIBE",
+    "UNSUBSCRIBE",
+    "OPTION",
+    "HELP",
+];
+
 impl ClientMessage {
     pub fn from_bytes(bytes: &Vec<u8>) -> Self {
-        let msg = String::from_utf8_lossy(bytes);
+        let msg = match std::str::from_utf8(bytes) {
+            Ok(msg) => msg,
+            Err(_) => return Self::ProtocolError(Problem::InvalidUtf8),
+        };
         let msg_split: Vec<&str> = msg.split_whitespace().collect();
 
         if msg_split.len() > 0 {
             match msg_split[0] {
-                "END" => {
-                    if msg_split.len() == 1 {
-                        Self::EndGame
-                    } else {
-                        Self::too_many_arguments("END")
-                    }
-                }
-                "MAP" => {
-                    if msg_split.len() == 1 {
-                        Self::GetMap
-                    } else {
-                        Self::too_many_arguments("MAP")
-                    }
-                }
-                "MOVE" => {
-                    if msg_split.len() == 2 {
-                        match msg_split[1] {
-                            "DOWN" => Self::Move(Direction::DOWN),
-                            "LEFT" => Self::Move(Direction::LEFT),
-                            "RIGHT" => Self::Move(Direction::RIGHT),
-                            "UP" => Self::Move(Direction::UP),
-                            invalid => Self::InvalidArguments(format!(
-                                "direction « {} » invalide",
-                                invalid
-                            )),
-                        }
-                    } else if msg_split.len() > 2 {
-                        Self::too_many_arguments("MOVE")
-                    } else {
-                        Self::not_enough_arguments("MOVE")
-                    }
-                }
+                "END" => Self::without_arguments(&msg_split, Self::EndGame),
+                "MAP" => Self::map(&msg_split),
+                "STATUS" => Self::without_arguments(&msg_split, Self::GetStatus),
+                "POS" => Self::without_arguments(&msg_split, Self::GetPosition),
+                "DIAMONDS" => Self::without_arguments(&msg_split, Self::GetDiamonds),
+                "TICK" => Self::without_arguments(&msg_split, Self::GetTick),
+                "HELP" => Self::without_arguments(&msg_split, Self::Help),
+                "MOVE" => Self::with_direction(&msg_split, Self::Move),
+                "DIG" => Self::with_direction(&msg_split, Self::Dig),
+                "WAIT" => Self::without_arguments(&msg_split, Self::Wait),
+                "PATH" => Self::path(&msg_split),
+                "CANCEL" => Self::without_arguments(&msg_split, Self::Cancel),
+                "SUBSCRIBE" => Self::with_events(&msg_split, Self::Subscribe),
+                "UNSUBSCRIBE" => Self::with_events(&msg_split, Self::Unsubscribe),
+                "OPTION" => Self::option(&msg_split),
                 cmd => Self::UnknownCommand(cmd.to_string()),
             }
         } else {
@@ -66,27 +413,299 @@ impl ClientMessage {
         }
     }
 
+    fn without_arguments(msg_split: &[&str], message: Self) -> Self {
+        if msg_split.len() == 1 {
+            message
+        } else {
+            Self::too_many_arguments(msg_split[0])
+        }
+    }
+
+    fn with_direction(msg_split: &[&str], message: fn(Direction) -> Self) -> Self {
+        if msg_split.len() == 2 {
+            match parse_direction(msg_split[1]) {
+                Some(direction) => message(direction),
+                None => Self::InvalidArguments(Problem::InvalidDirection(msg_split[1].to_string())),
+            }
+        } else if msg_split.len() > 2 {
+            Self::too_many_arguments(msg_split[0])
+        } else {
+            Self::not_enough_arguments(msg_split[0])
+        }
+    }
+
+    // MAP seul renvoie toute la carte, MAP SINCE <tick> seulement les cases modifiées depuis
+    fn map(msg_split: &[&str]) -> Self {
+        match msg_split.len() {
+            1 => Self::GetMap,
+            2 if msg_split[1] == "SINCE" => Self::not_enough_arguments("MAP SINCE"),
+            3 if msg_split[1] == "SINCE" => match u32::from_str(msg_split[2]) {
+                Ok(tick) => Self::GetMapSince(tick),
+                Err(_) => Self::InvalidArguments(Problem::InvalidTick(msg_split[2].to_string())),
+            },
+            _ => Self::too_many_arguments("MAP"),
+        }
+    }
+
+    // les étapes sont soit une direction (comme MOVE), soit DIG:<direction>, soit WAIT
+    fn path(msg_split: &[&str]) -> Self {
+        if msg_split.len() < 2 {
+            return Self::not_enough_arguments("PATH");
+        }
+        if msg_split.len() > MAX_PATH_LENGTH + 1 {
+            return Self::too_many_arguments("PATH");
+        }
+
+        let mut steps = Vec::with_capacity(msg_split.len() - 1);
+        for arg in &msg_split[1..] {
+            let step = match arg.strip_prefix("DIG:") {
+                Some(direction) => parse_direction(direction).map(Step::Dig),
+                None if *arg == "WAIT" => Some(Step::Wait),
+                None => parse_direction(arg).map(Step::Move),
+            };
+            match step {
+                Some(step) => steps.push(step),
+                None => return Self::InvalidArguments(Problem::InvalidStep(arg.to_string())),
+            }
+        }
+        Self::Path(steps)
+    }
+
+    fn option(msg_split: &[&str]) -> Self {
+        if msg_split.len() < 3 {
+            Self::not_enough_arguments("OPTION")
+        } else if msg_split.len() > 3 {
+            Self::too_many_arguments("OPTION")
+        } else {
+            match ProtocolOption::parse(msg_split[1], msg_split[2]) {
+                Some(option) => Self::SetOption(option),
+                None => Self::InvalidArguments(Problem::InvalidOption(
+                    msg_split[1].to_string(),
+                    msg_split[2].to_string(),
+                )),
+            }
+        }
+    }
+
+    // ALL désigne tous les types d'évènements
+    fn with_events(msg_split: &[&str], message: fn(Vec<EventKind>) -> Self) -> Self {
+        if msg_split.len() < 2 {
+            return Self::not_enough_arguments(msg_split[0]);
+        }
+
+        let mut kinds = Vec::new();
+        for arg in &msg_split[1..] {
+            if *arg == "ALL" {
+                kinds.extend_from_slice(&EVENT_KINDS);
+            } else {
+                match EVENT_KINDS.iter().find(|kind| kind.name() == *arg) {
+                    Some(kind) => kinds.push(*kind),
+                    None => return Self::InvalidArguments(Problem::UnknownEvent(arg.to_string())),
+                }
+            }
+        }
+        message(kinds)
+    }
+
     pub fn not_enough_arguments(command: &str) -> Self {
-        Self::InvalidArguments(format!(
-            "arguments manquants pour la commande « {} »",
-            command
-        ))
+        Self::InvalidArguments(Problem::MissingArguments(command.to_string()))
     }
 
     pub fn too_many_arguments(command: &str) -> Self {
-        Self::InvalidArguments(format!("trop d'arguments pour la commande « {} »", command))
+        Self::InvalidArguments(Problem::TooManyArguments(command.to_string()))
+    }
+}
+
+// les cases sont envoyées sous la forme x,y,L avec les lettres de MAP,
+// sauf les cases vides qui sont notées _ pour ne pas être confondues avec le séparateur
+fn encode_cells(cells: &[(Pos, char)]) -> String {
+    let mut encoded = Vec::with_capacity(cells.len());
+    for (pos, letter) in cells {
+        encoded.push(format!(
+            "{},{},{}",
+            pos.x,
+            pos.y,
+            if *letter == ' ' { '_' } else { *letter }
+        ));
+    }
+    encoded.join(" ")
+}
+
+fn parse_direction(direction: &str) -> Option<Direction> {
+    match direction {
+        "DOWN" => Some(Direction::DOWN),
+        "LEFT" => Some(Direction::LEFT),
+        "RIGHT" => Some(Direction::RIGHT),
+        "UP" => Some(Direction::UP),
+        _ => None,
     }
 }
 
 impl ServerMessage {
-    pub fn into_bytes(self) -> Vec<u8> {
+    // les textes d'erreur sont écrits dans la langue choisie par le client, s'il en a choisi une
+    pub fn into_bytes(self, encoding: &Encoding) -> Vec<u8> {
+        let language = encoding.language;
         match self {
-            Self::Error(message) => format!("NOK {}", message),
-            Self::ValidMove => "OK".to_string(),
-            Self::MapResponse(map) => map,
+            Self::MapResponse(map) => return map.encode(),
+            Self::MapSnapshot(tick, map) => {
+                let mut bytes = format!("FULL {}", tick).into_bytes();
+                bytes.push(if let MapData::Text { rows: false, .. } = map {
+                    b' '
+                } else {
+                    b'\n'
+                });
+                bytes.extend(map.encode());
+                return bytes;
+            }
+            Self::Error(problem) => format!("NOK {}", problem.encode(language)),
+            Self::ActionDone(outcome) => match encoding.replies {
+                Replies::Plain => "OK".to_string(),
+                Replies::Detailed => format!("OK {}", outcome.name()),
+            },
+            Self::MapDelta(tick, cells) => {
+                let mut response = format!("DELTA {} {}", tick, cells.len());
+                if !cells.is_empty() {
+                    response.push(' ');
+                    response.push_str(&encode_cells(&cells));
+                }
+                response
+            }
+            Self::Bye(reason) => format!("BYE {}", reason.encode(language)),
+            Self::OptionSet(option) => format!("OK OPTION {} {}", option.name(), option.value()),
             Self::EndGame => "END".to_string(),
             Self::EndConnection => "".to_string(),
+            Self::Status(state, exit_unlocked) => format!(
+                "STATUS {} {}",
+                state,
+                if exit_unlocked { "UNLOCKED" } else { "LOCKED" }
+            ),
+            Self::Position(pos) => format!("POS {} {}", pos.x, pos.y),
+            Self::Diamonds(diamonds) => {
+                let mut response = format!("DIAMONDS {}", diamonds.len());
+                for d in diamonds {
+                    response.push_str(&format!(" {},{}", d.x, d.y));
+                }
+                response
+            }
+            Self::Tick(tick) => format!("TICK {}", tick),
+            Self::Help => format!("HELP {}", COMMANDS.join(" ")),
+            Self::Queued(count) => format!("OK QUEUED {}", count),
+            Self::StepDone(index, outcome) => format!("STEP {} {}", index, outcome.name()),
+            Self::StepFailed(index, problem) => {
+                format!("STEP {} NOK {}", index, problem.encode(language))
+            }
+            Self::Cancelled(count) => format!("OK CANCELLED {}", count),
+            Self::PathDone(count) => format!("DONE {}", count),
+            Self::Subscriptions(kinds) => {
+                let mut response = "OK EVENTS".to_string();
+                for kind in kinds {
+                    response.push(' ');
+                    response.push_str(kind.name());
+                }
+                response
+            }
+            Self::Event(event) => match event {
+                GameEvent::Beat(tick) => format!("EVENT BEAT {}", tick),
+                GameEvent::Diamond(pos, left) => {
+                    format!("EVENT DIAMOND {} {} {}", pos.x, pos.y, left)
+                }
+                GameEvent::ExitUnlocked => "EVENT EXIT UNLOCKED".to_string(),
+                GameEvent::Player(pos) => format!("EVENT PLAYER {} {}", pos.x, pos.y),
+                GameEvent::Cells(cells) => format!("EVENT CELLS {}", encode_cells(&cells)),
+                GameEvent::End(state) => format!("EVENT END {}", state),
+            },
         }
         .into_bytes()
     }
+
+    // en trames binaires, chaque trame commence par son type : T pour du texte, M pour une carte
+    pub fn into_frames(self, encoding: &Encoding) -> Vec<Vec<u8>> {
+        match self {
+            Self::MapResponse(MapData::Binary(map)) => vec![frame(b'M', map)],
+            Self::MapSnapshot(tick, MapData::Binary(map)) => vec![
+                frame(b'T', format!("FULL {}", tick).into_bytes()),
+                frame(b'M', map),
+            ],
+            other => vec![frame(b'T', other.into_bytes(encoding))],
+        }
+    }
+}
+
+fn frame(kind: u8, data: Vec<u8>) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(data.len() + 1);
+    frame.push(kind);
+    frame.extend(data);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::write_message;
+
+    fn text(messages: Vec<ServerMessage>) -> String {
+        let mut encoding = Encoding::new();
+        let mut stream = Vec::new();
+        for msg in messages {
+            write_message(&mut stream, msg, &mut encoding).unwrap();
+        }
+        String::from_utf8(stream).unwrap()
+    }
+
+    #[test]
+    fn option_replies_is_parsed() {
+        for (line, replies) in [
+            ("OPTION REPLIES PLAIN", Replies::Plain),
+            ("OPTION REPLIES DETAILED", Replies::Detailed),
+        ] {
+            match ClientMessage::from_bytes(&line.as_bytes().to_vec()) {
+                ClientMessage::SetOption(option) => {
+                    assert_eq!(option, ProtocolOption::Replies(replies))
+                }
+                other => panic!("{} : {:?}", line, other),
+            }
+        }
+        assert!(matches!(
+            ClientMessage::from_bytes(&b"OPTION REPLIES FULL".to_vec()),
+            ClientMessage::InvalidArguments(Problem::InvalidOption(_, _))
+        ));
+    }
+
+    #[test]
+    fn plain_replies_by_default() {
+        let replies = text(vec![
+            ServerMessage::ActionDone(Outcome::Moved),
+            ServerMessage::ActionDone(Outcome::Dug),
+            ServerMessage::ActionDone(Outcome::Exit),
+        ]);
+        assert_eq!(replies, "OK\nOK\nOK\n");
+    }
+
+    #[test]
+    fn detailed_replies_after_option() {
+        let replies = text(vec![
+            ServerMessage::ActionDone(Outcome::Moved),
+            ServerMessage::OptionSet(ProtocolOption::Replies(Replies::Detailed)),
+            ServerMessage::ActionDone(Outcome::Moved),
+            ServerMessage::ActionDone(Outcome::Dug),
+            ServerMessage::ActionDone(Outcome::Diamond),
+            ServerMessage::ActionDone(Outcome::Exit),
+            ServerMessage::ActionDone(Outcome::Waited),
+            ServerMessage::OptionSet(ProtocolOption::Replies(Replies::Plain)),
+            ServerMessage::ActionDone(Outcome::Dug),
+        ]);
+        assert_eq!(
+            replies,
+            "OK\nOK OPTION REPLIES DETAILED\nOK MOVED\nOK DUG\nOK DIAMOND\nOK EXIT\nOK WAITED\n\
+             OK OPTION REPLIES PLAIN\nOK\n"
+        );
+    }
+
+    #[test]
+    fn steps_are_always_detailed() {
+        assert_eq!(
+            text(vec![ServerMessage::StepDone(1, Outcome::Dug)]),
+            "STEP 1 DUG\n"
+        );
+    }
 }
@@ -0,0 +1,223 @@
+use crate::args::option;
+use crate::interface::Problem;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+// limites imposées à chaque client pour qu'un bot bloqué ne garde pas la partie indéfiniment
+#[derive(Copy, Clone)]
+pub struct Limits {
+    pub idle_timeout: Duration,
+    pub game_timeout: Duration,
+    pub commands_per_beat: u32,
+    pub refused_before_disconnect: u32,
+    pub beat: Duration,
+}
+
+impl Limits {
+    pub fn new(beat: Duration) -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(120),
+            game_timeout: Duration::from_secs(30 * 60),
+            commands_per_beat: 4,
+            refused_before_disconnect: 20,
+            beat,
+        }
+    }
+
+    // options reconnues : --idle-timeout <s>, --game-timeout <s>, --commands-per-beat <n>
+    // et --max-refused <n> ; les valeurs nulles, invalides ou trop grandes sont ignorées
+    pub fn with_args(mut self, args: &[String]) -> Self {
+        let number = |key| {
+            let value = option(args, key)?;
+            u32::from_str(value).ok().filter(|n| *n > 0).or_else(|| {
+                warn!("option « {} {} » ignorée", key, value);
+                None
+            })
+        };
+        if let Some(secs) = number("--idle-timeout") {
+            self.idle_timeout = Duration::from_secs(secs as u64);
+        }
+        if let Some(secs) = number("--game-timeout") {
+            self.game_timeout = Duration::from_secs(secs as u64);
+        }
+        if let Some(n) = number("--commands-per-beat") {
+            self.commands_per_beat = n;
+        }
+        if let Some(n) = number("--max-refused") {
+            self.refused_before_disconnect = n;
+        }
+        self
+    }
+}
+
+pub enum Verdict {
+    Accepted,
+    Refused,
+    Exceeded(Problem),
+}
+
+// surveille un client pendant toute la durée de sa connexion
+pub struct Watchdog {
+    limits: Limits,
+    started: Instant,
+    last_command: Instant,
+    window_start: Instant,
+    window_commands: u32,
+    refused: u32,
+}
+
+impl Watchdog {
+    pub fn new(limits: Limits) -> Self {
+        let now = Instant::now();
+        Self {
+            limits,
+            started: now,
+            last_command: now,
+            window_start: now,
+            window_commands: 0,
+            refused: 0,
+        }
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    // le compteur de commandes refusées repart de zéro après un temps sans dépassement
+    pub fn command_received(&mut self) -> Verdict {
+        let now = Instant::now();
+        self.last_command = now;
+
+        if now.duration_since(self.window_start) >= self.limits.beat {
+            if self.window_commands <= self.limits.commands_per_beat {
+                self.refused = 0;
+            }
+            self.window_start = now;
+            self.window_commands = 0;
+        }
+
+        self.window_commands += 1;
+        if self.window_commands <= self.limits.commands_per_beat {
+            Verdict::Accepted
+        } else {
+            self.refused += 1;
+            if self.refused > self.limits.refused_before_disconnect {
+                Verdict::Exceeded(Problem::TooManyRefused)
+            } else {
+                Verdict::Refused
+            }
+        }
+    }
+
+    pub fn check_timeouts(&self) -> Option<Problem> {
+        if self.started.elapsed() > self.limits.game_timeout {
+            Some(Problem::GameTimeout)
+        } else if self.last_command.elapsed() > self.limits.idle_timeout {
+            Some(Problem::IdleTimeout)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    const BEAT: Duration = Duration::from_millis(500);
+
+    fn parse(line: &str) -> Limits {
+        let args: Vec<String> = line.split_whitespace().map(String::from).collect();
+        Limits::new(BEAT).with_args(&args)
+    }
+
+    #[test]
+    fn defaults_without_options() {
+        let limits = parse("");
+        assert_eq!(limits.idle_timeout, Duration::from_secs(120));
+        assert_eq!(limits.game_timeout, Duration::from_secs(30 * 60));
+        assert_eq!(limits.commands_per_beat, 4);
+        assert_eq!(limits.refused_before_disconnect, 20);
+        assert_eq!(limits.beat, BEAT);
+    }
+
+    #[test]
+    fn options_override_defaults() {
+        let limits =
+            parse("--idle-timeout 5 --game-timeout 60 --commands-per-beat 2 --max-refused 3");
+        assert_eq!(limits.idle_timeout, Duration::from_secs(5));
+        assert_eq!(limits.game_timeout, Duration::from_secs(60));
+        assert_eq!(limits.commands_per_beat, 2);
+        assert_eq!(limits.refused_before_disconnect, 3);
+    }
+
+    #[test]
+    fn zero_invalid_and_too_large_values_are_ignored() {
+        let limits = parse(
+            "--idle-timeout 0 --game-timeout -1 --commands-per-beat 4294967296 --max-refused x",
+        );
+        assert_eq!(limits.idle_timeout, Duration::from_secs(120));
+        assert_eq!(limits.game_timeout, Duration::from_secs(30 * 60));
+        assert_eq!(limits.commands_per_beat, 4);
+        assert_eq!(limits.refused_before_disconnect, 20);
+
+        let limits = parse("--commands-per-beat 0");
+        assert_eq!(limits.commands_per_beat, 4);
+    }
+
+    fn watchdog(
+        commands_per_beat: u32,
+        refused_before_disconnect: u32,
+        beat: Duration,
+    ) -> Watchdog {
+        Watchdog::new(Limits {
+            commands_per_beat,
+            refused_before_disconnect,
+            beat,
+            ..Limits::new(beat)
+        })
+    }
+
+    #[test]
+    fn commands_beyond_the_limit_are_refused_then_disconnect() {
+        let mut watchdog = watchdog(2, 1, Duration::from_secs(3600));
+        assert!(matches!(watchdog.command_received(), Verdict::Accepted));
+        assert!(matches!(watchdog.command_received(), Verdict::Accepted));
+        assert!(matches!(watchdog.command_received(), Verdict::Refused));
+        assert!(matches!(
+            watchdog.command_received(),
+            Verdict::Exceeded(Problem::TooManyRefused)
+        ));
+    }
+
+    #[test]
+    fn limit_starts_again_on_the_next_beat() {
+        let beat = Duration::from_millis(20);
+        let mut watchdog = watchdog(1, 5, beat);
+        assert!(matches!(watchdog.command_received(), Verdict::Accepted));
+        assert!(matches!(watchdog.command_received(), Verdict::Refused));
+        sleep(beat * 2);
+        assert!(matches!(watchdog.command_received(), Verdict::Accepted));
+    }
+
+    #[test]
+    fn timeouts() {
+        let mut watchdog = Watchdog::new(Limits {
+            idle_timeout: Duration::from_millis(20),
+            ..Limits::new(BEAT)
+        });
+        assert_eq!(watchdog.check_timeouts(), None);
+        sleep(Duration::from_millis(40));
+        assert_eq!(watchdog.check_timeouts(), Some(Problem::IdleTimeout));
+        watchdog.command_received();
+        assert_eq!(watchdog.check_timeouts(), None);
+
+        let watchdog = Watchdog::new(Limits {
+            game_timeout: Duration::from_millis(20),
+            ..Limits::new(BEAT)
+        });
+        sleep(Duration::from_millis(40));
+        assert_eq!(watchdog.check_timeouts(), Some(Problem::GameTimeout));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::args::option;
+use std::cell::Cell;
+use std::fmt;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// journal du serveur : chaque ligne porte une date, un niveau et, si elle a été écrite
+// pendant une connexion, l'identifiant de celle-ci
+
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Error, format_args!($($arg)*)) };
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Warn, format_args!($($arg)*)) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Info, format_args!($($arg)*)) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::log::write($crate::log::Level::Debug, format_args!($($arg)*)) };
+}
+
+#[derive(Copy, Clone, PartialEq, PartialOrd)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+struct Logger {
+    level: Level,
+    format: Format,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+thread_local! {
+    static CONNECTION: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+// options reconnues : --log-level <error|warn|info|debug> et --log-format <text|json>
+pub fn init(args: &[String]) {
+    let mut logger = Logger {
+        level: Level::Info,
+        format: Format::Text,
+    };
+    let mut invalid = Vec::new();
+
+    match option(args, "--log-level") {
+        Some("error") => logger.level = Level::Error,
+        Some("warn") => logger.level = Level::Warn,
+        Some("info") => logger.level = Level::Info,
+        Some("debug") => logger.level = Level::Debug,
+        Some(value) => invalid.push(format!("--log-level {}", value)),
+        None => {}
+    }
+    match option(args, "--log-format") {
+        Some("text") => logger.format = Format::Text,
+        Some("json") => logger.format = Format::Json,
+        Some(value) => invalid.push(format!("--log-format {}", value)),
+        None => {}
+    }
+
+    LOGGER.get_or_init(|| logger);
+    for option in invalid {
+        warn!("option « {} » ignorée", option);
+    }
+}
+
+// les messages écrits par ce thread seront rattachés à cette connexion
+pub fn set_connection(connection: Option<u32>) {
+    CONNECTION.with(|c| c.set(connection));
+}
+
+fn enabled(level: Level) -> bool {
+    match LOGGER.get() {
+        Some(logger) => level <= logger.level,
+        None => level <= Level::Info,
+    }
+}
+
+pub fn write(level: Level, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+
+    let format = LOGGER.get().map_or(Format::Text, |logger| logger.format);
+    let connection = CONNECTION.with(|c| c.get());
+    let timestamp = timestamp();
+    let message = args.to_string();
+
+    let line = match format {
+        Format::Text => match connection {
+            Some(id) => format!("{} {:<5} [#{}] {}", timestamp, level.name(), id, message),
+            None => format!("{} {:<5} {}", timestamp, level.name(), message),
+        },
+        Format::Json => format!(
+            "{{\"time\":\"{}\",\"level\":\"{}\",\"connection\":{},\"message\":\"{}\"}}",
+            timestamp,
+            level.name(),
+            connection.map_or("null".to_string(), |id| id.to_string()),
+            escape_json(&message)
+        ),
+    };
+
+    // une ligne de journal perdue ne doit pas interrompre le jeu
+    writeln!(std::io::stdout().lock(), "{}", line).unwrap_or(());
+}
+
+// date UTC au format ISO 8601, avec les millisecondes
+pub fn timestamp() -> String {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+// conversion d'un nombre de jours depuis le 1er janvier 1970 en date du calendrier grégorien
+// (algorithme de Howard Hinnant)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
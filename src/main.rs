@@ -5,23 +5,55 @@ extern crate include_res;
 extern crate oorandom;
 extern crate sdl2;
 
-use sdl2::event::Event;
+use resource::pack;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::rwops::RWops;
+use sdl2::render::WindowCanvas;
+use sdl2::video::FullscreenType;
 use std::io::ErrorKind;
 use std::net::{Shutdown, TcpListener};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::mpsc::{channel, TryRecvError};
+use std::sync::Arc;
 use std::time::Duration;
 
+#[macro_use]
+mod log;
+
+mod args;
 mod clock;
+mod error;
+mod framing;
 mod game;
 mod home;
+mod i18n;
 mod interface;
+mod limits;
 mod network;
 mod resource;
+mod settings;
+mod transcript;
+
+// résolution dans laquelle tout est dessiné, quelle que soit la taille de la fenêtre
+const LOGICAL_SIZE: (u32, u32) = (1440, 810);
 
 pub fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    log::init(&args);
+    args::check(&args);
+    let settings_path = settings::Settings::path(&args);
+    let mut settings = settings::Settings::load(&settings_path);
+    // la langue de --lang ne vaut que pour cette exécution : le fichier garde la sienne tant
+    // qu'une autre n'est pas choisie depuis l'écran des paramètres
+    let language_override = i18n::Language::from_args(&args);
+    let mut file_language = language_override.map(|_| settings.language);
+    if let Some(language) = language_override {
+        settings.language = language;
+    }
+
+    let pack = pack::AssetPack::from_args(&args);
+
     // initialisation de SDL2
     let sdl_context = sdl2::init().unwrap();
 
@@ -29,12 +61,23 @@ pub fn main() {
     let video_subsystem = sdl_context.video().unwrap();
 
     let window = video_subsystem
-        .window("Crypt Of The RustDancer", 1440, 810)
+        .window("Crypt Of The RustDancer", LOGICAL_SIZE.0, LOGICAL_SIZE.1)
         .position_centered()
+        .resizable()
         .build()
         .unwrap();
 
     let mut canvas = window.into_canvas().build().unwrap();
+    canvas
+        .window_mut()
+        .set_minimum_size(LOGICAL_SIZE.0 / 2, LOGICAL_SIZE.1 / 2)
+        .unwrap_or(());
+    // l'affichage garde sa taille logique, même en plein écran
+    canvas
+        .set_logical_size(LOGICAL_SIZE.0, LOGICAL_SIZE.1)
+        .unwrap();
+    set_fullscreen(&mut canvas, settings.fullscreen);
+    set_scaling(&mut canvas, settings.integer_scaling);
     canvas.set_draw_color(Color::RGB(0, 0, 0));
     let texture_creator = canvas.texture_creator();
 
@@ -44,56 +87,65 @@ pub fn main() {
     // configuration du système de texte
     let ttf_context = sdl2::ttf::init().unwrap();
 
-    let text_renderer = resource::text::TextRenderer::new(
-        ttf_context
-            .load_font_from_rwops(
-                RWops::from_bytes(include_bytes!("../res/Minecraftia-Regular.ttf")).unwrap(),
-                20,
+    // la police d'un pack reste chargée jusqu'à la fin du programme
+    let text_renderer = pack
+        .as_ref()
+        .and_then(|pack| {
+            let font = Box::leak(pack.read(pack::FONT)?.into_boxed_slice());
+            resource::text::TextRenderer::new(&ttf_context, font, &texture_creator)
+                .map_err(|err| pack.failed(pack::FONT, err))
+                .ok()
+        })
+        .unwrap_or_else(|| {
+            resource::text::TextRenderer::new(
+                &ttf_context,
+                include_bytes!("../res/Minecraftia-Regular.ttf"),
+                &texture_creator,
             )
-            .unwrap(),
-        &texture_creator,
-    );
+            .unwrap()
+        });
 
     // écran de chargement
     canvas.clear();
     {
-        let message = text_renderer.render(
-            "Chargement ...",
+        text_renderer.draw(
+            &mut canvas,
+            settings.language.text(i18n::Text::Loading),
             resource::text::TextRenderingFormat::Shaded(
                 Color::RGB(255, 255, 255),
                 Color::RGB(0, 0, 0),
             ),
+            (720, 405),
+            resource::text::Anchor::Center,
         );
-        canvas
-            .copy(
-                message.texture(),
-                None,
-                Rect::new(
-                    720 - message.width() as i32 / 2,
-                    405 - message.height() as i32 / 2,
-                    message.width(),
-                    message.height(),
-                ),
-            )
-            .unwrap();
         canvas.present();
     }
 
     // chargement des images
-    let images = resource::image::Images::load(&texture_creator);
+    let images = resource::image::Images::load(&texture_creator, pack.as_ref());
+
+    // terrain de la partie, dessiné une fois pour toutes tant qu'il ne change pas
+    let mut terrain = if game::terrain::enabled(&args) {
+        Some(game::terrain::TerrainCache::new(&texture_creator))
+    } else {
+        None
+    };
 
     // chargements de la musique
-    let sounds = resource::audio::Sounds::load();
+    let sounds = resource::audio::Sounds::load(pack.as_ref());
+    sounds.set_volume(settings.volume);
     sounds.play_menu_music();
 
+    let mut listener = listen(settings.port).unwrap();
+
     // différents écrans
-    let mut home = home::Home::new();
+    let mut home = home::Home::new(settings, video_subsystem.clipboard());
     let mut game = game::Game::new();
     // jeu en cours
     let mut ingame = false;
 
-    let listener = TcpListener::bind("127.0.0.1:54321").unwrap();
-    listener.set_nonblocking(true).unwrap();
+    // copie du transcript de la connexion en cours, pour dater les messages traités
+    let mut transcript: Option<transcript::Transcript> = None;
     let mut handles: Option<
         network::ThreadHandles<interface::ServerMessage, interface::ClientMessage, ()>,
     > = None;
@@ -101,7 +153,12 @@ pub fn main() {
 
     let mut event_pump = sdl_context.event_pump().unwrap();
     let frame_duration = Duration::from_millis(30);
-    let mut clock = clock::Clock::new(Duration::from_millis(125)); // corresponds à 120 BPM : 60s / (0,125s * 4 temps) = 120 BPM
+    let tick_duration = home.settings().tick_duration(); // 125 ms à 120 BPM : 60s / (0,125s * 4 temps) = 120 BPM
+    let mut clock = clock::Clock::new(tick_duration);
+    let limits = limits::Limits::new(tick_duration * 4).with_args(&args);
+    let transcripts = transcript::directory(&args);
+    // tick de la partie, lu par le thread réseau pour dater les transcripts
+    let shared_tick = Arc::new(AtomicU32::new(0));
 
     'running: loop {
         for event in event_pump.poll_iter() {
@@ -109,6 +166,15 @@ pub fn main() {
                 Event::Quit { .. } => {
                     break 'running;
                 }
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } => set_scaling(&mut canvas, home.settings().integer_scaling),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F11),
+                    repeat: false,
+                    ..
+                } => home.toggle_fullscreen(),
                 ev => {
                     if ingame {
                         game.handle_event(ev);
@@ -119,29 +185,81 @@ pub fn main() {
             }
         }
 
-        if ingame {
+        // les paramètres qui ne concernent pas la partie s'appliquent immédiatement
+        if home.settings_changed() {
+            let settings = home.settings();
+            let mut saved = settings.clone();
+            match file_language {
+                Some(language) if Some(settings.language) == language_override => {
+                    saved.language = language
+                }
+                _ => file_language = None,
+            }
+            if let Err(err) = saved.save(&settings_path) {
+                warn!(
+                    "impossible d'enregistrer les paramètres dans {} : {}",
+                    settings_path.display(),
+                    err
+                );
+            }
+            sounds.set_volume(settings.volume);
+            set_fullscreen(&mut canvas, settings.fullscreen);
+            set_scaling(&mut canvas, settings.integer_scaling);
+            if listener.local_addr().map(|addr| addr.port()).ok() != Some(settings.port) {
+                match listen(settings.port) {
+                    Ok(new_listener) => listener = new_listener,
+                    Err(err) => warn!(
+                        "impossible d'écouter sur le port {} : {}",
+                        settings.port, err
+                    ),
+                }
+            }
+        }
+
+        if let Some(session) = handles.as_ref() {
+            let mut session_ended = false;
             while clock.tick() {
-                if game.tick() {
-                    match handles.as_ref().unwrap().rx.try_recv() {
+                let receiving = game.tick();
+                shared_tick.store(game.current_tick(), Ordering::Relaxed);
+                if receiving {
+                    match session.rx.try_recv() {
                         Ok(interface::ClientMessage::ConnectionEnded) => {
-                            handles.unwrap().join_handle.join().unwrap();
-                            handles = None;
-                            ingame = false;
-                            sounds.play_menu_music();
+                            session_ended = true;
                             break;
                         }
-                        Ok(other) => game.react_to_message(other),
+                        Ok(other) => {
+                            if let Some(recording) = transcript.as_mut() {
+                                if let Err(err) = recording.processed(game.current_tick()) {
+                                    warn!("arrêt de l'enregistrement de la connexion : {}", err);
+                                    transcript = None;
+                                }
+                            }
+                            game.react_to_message(other)
+                        }
                         Err(TryRecvError::Empty) => {}
                         Err(TryRecvError::Disconnected) => {
-                            panic!("le thread réseau a paniqué de manière inattendue")
+                            error!("le thread réseau s'est arrêté sans prévenir");
+                            session_ended = true;
+                            break;
                         }
                     }
                 }
-                match game.response() {
-                    Some(message) => handles.as_ref().unwrap().tx.send(message).unwrap_or(()),
-                    None => {}
+                while let Some(message) = game.response() {
+                    session.tx.send(message).unwrap_or(());
                 }
             }
+
+            if session_ended {
+                if let Some(session) = handles.take() {
+                    if let Err(err) = session.join() {
+                        error!("{}", err);
+                    }
+                }
+                log::set_connection(None);
+                transcript = None;
+                ingame = false;
+                sounds.play_menu_music();
+            }
         }
 
         match listener.accept() {
@@ -151,50 +269,124 @@ pub fn main() {
                     let (tx2, rx1) = channel();
                     let (tx1, rx2) = channel();
 
-                    let join_handle = threads
-                        .new_thread()
-                        // le nouveau thread prends une extrémité de chaque canal ...
-                        .spawn(move || network::handle_client(socket, tx2, rx2))
-                        .unwrap();
-
-                    // ... et ce thread prends les autres
-                    handles = Some(network::ThreadHandles {
-                        rx: rx1,
-                        tx: tx1,
-                        join_handle,
+                    let (connection, builder) = threads.new_thread();
+                    let tick_duration = home.settings().tick_duration();
+                    let limits = limits::Limits {
+                        beat: tick_duration * 4,
+                        ..limits
+                    };
+                    clock = clock::Clock::new(tick_duration);
+                    let seed = home.seed();
+                    game.reset(seed, home.settings());
+                    home.seed_played();
+                    shared_tick.store(game.current_tick(), Ordering::Relaxed);
+                    transcript = transcripts.as_ref().and_then(|directory| {
+                        transcript::Transcript::create(
+                            directory,
+                            connection,
+                            shared_tick.clone(),
+                            seed,
+                            home.settings(),
+                        )
+                        .map_err(|err| warn!("impossible d'enregistrer la connexion : {}", err))
+                        .ok()
                     });
+                    let network_transcript = transcript.clone();
 
-                    println!("connecté au client @{}", addr);
-                    ingame = true;
-                    game.reset(home.seed());
-                    sounds.play_in_game_music();
+                    let spawned = builder
+                        // le nouveau thread prends une extrémité de chaque canal ...
+                        .spawn(move || {
+                            network::handle_client(
+                                socket,
+                                tx2,
+                                rx2,
+                                limits,
+                                connection,
+                                network_transcript,
+                            )
+                        });
+
+                    match spawned {
+                        Ok(join_handle) => {
+                            // ... et ce thread prends les autres
+                            handles = Some(network::ThreadHandles {
+                                rx: rx1,
+                                tx: tx1,
+                                join_handle,
+                            });
+
+                            log::set_connection(Some(connection));
+                            info!("connecté au client @{}", addr);
+                            ingame = true;
+                            sounds.play_in_game_music();
+                        }
+                        Err(err) => error!("{}", error::Error::ThreadNotStarted(err)),
+                    }
                 } else {
                     // refuser la connexion si une partie est déja en cours
-                    socket.shutdown(Shutdown::Both).unwrap();
+                    socket.shutdown(Shutdown::Both).unwrap_or(());
                 }
             }
             Err(e) if e.kind() == ErrorKind::WouldBlock => {}
-            Err(e) => println!("impossible de se connecter au client: {}", e),
+            Err(e) => warn!("impossible de se connecter au client: {}", e),
         }
 
+        let language = home.settings().language;
         canvas.clear();
         if ingame {
-            game.draw(&mut canvas, &images, &text_renderer);
+            game.draw(
+                &mut canvas,
+                &images,
+                &text_renderer,
+                terrain.as_mut(),
+                language,
+            );
         } else {
-            home.draw(&mut canvas, &images, &text_renderer);
+            home.draw(&mut canvas, &images, &text_renderer, language);
         }
         canvas.present();
+        text_renderer.end_frame();
 
         std::thread::sleep(frame_duration);
     }
 
-    if ingame {
-        handles
-            .as_ref()
-            .unwrap()
+    if let Some(session) = handles {
+        session
             .tx
             .send(interface::ServerMessage::EndConnection)
-            .unwrap();
-        handles.unwrap().join_handle.join().unwrap();
+            .unwrap_or(());
+        if let Err(err) = session.join() {
+            error!("{}", err);
+        }
+    }
+}
+
+fn listen(port: u16) -> Result<TcpListener, std::io::Error> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    info!("en écoute sur le port {}", port);
+    Ok(listener)
+}
+
+// plein écran « bureau » : la résolution de l'écran n'est pas modifiée
+// l'agrandissement entier n'est possible que si la fenêtre contient la taille logique :
+// une fenêtre plus petite est réduite normalement
+fn set_scaling(canvas: &mut WindowCanvas, integer: bool) {
+    let fits = canvas
+        .output_size()
+        .is_ok_and(|(width, height)| width >= LOGICAL_SIZE.0 && height >= LOGICAL_SIZE.1);
+    if let Err(err) = canvas.set_integer_scale(integer && fits) {
+        warn!("impossible de changer la mise à l'échelle : {}", err);
+    }
+}
+
+fn set_fullscreen(canvas: &mut WindowCanvas, fullscreen: bool) {
+    let mode = if fullscreen {
+        FullscreenType::Desktop
+    } else {
+        FullscreenType::Off
+    };
+    if let Err(err) = canvas.window_mut().set_fullscreen(mode) {
+        warn!("impossible de changer le mode d'affichage : {}", err);
     }
 }
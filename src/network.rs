@@ -1,128 +1,223 @@
-use crate::interface::{ClientMessage, ServerMessage};
-use std::io::{ErrorKind, Read, Write};
+use crate::error::Error;
+use crate::framing::{write_message, Encoding, Incoming, MessageReader};
+use crate::i18n::Language;
+use crate::interface::{ClientMessage, Framing, Problem, ServerMessage};
+use crate::limits::{Limits, Verdict, Watchdog};
+use crate::log;
+use crate::transcript::Transcript;
+use std::io::{ErrorKind, Write};
 use std::net::{Shutdown, TcpStream};
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
 use std::thread::{Builder, JoinHandle};
 use std::time::Duration;
 
+// intervalle entre deux vérifications des messages à envoyer sans commande du client
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// un client qui ne lit plus ses messages pendant ce nombre de temps est déconnecté : sans
+// cette limite, l'écriture bloquerait le thread réseau quand le tampon du socket est plein
+const WRITE_TIMEOUT_BEATS: u32 = 8;
+
+// raisons pour lesquelles une connexion se termine normalement
+enum Ending {
+    ClientLeft,
+    Closed,
+    LimitExceeded(Problem),
+}
+
+struct Session {
+    socket: TcpStream,
+    tx: Sender<ClientMessage>,
+    rx: Receiver<ServerMessage>,
+    encoding: Encoding,
+    reader: MessageReader,
+    watchdog: Watchdog,
+    transcript: Option<Transcript>,
+    msg: Vec<u8>,
+}
+
 pub fn handle_client(
-    mut socket: TcpStream,
+    socket: TcpStream,
     tx: Sender<ClientMessage>,
     rx: Receiver<ServerMessage>,
+    limits: Limits,
+    connection: u32,
+    transcript: Option<Transcript>,
 ) {
-    socket.set_nonblocking(false).unwrap(); // Windows crée des sockets non-bloquants par défaut
-    socket
-        .set_read_timeout(Some(Duration::from_secs(5)))
-        .unwrap();
-
-    socket.write(b"D\xc3\x89BUT\n").unwrap();
-
-    let mut msg = Vec::new();
-
-    while match read_line(&mut socket, &mut msg) {
-        Ok(size) => {
-            if size == -1 {
-                println!(
-                    "le client @{} a mis fin à la connexion",
-                    get_peer_address(&socket),
-                );
-                socket.shutdown(Shutdown::Both).unwrap();
-                tx.send(ClientMessage::ConnectionEnded {}).unwrap();
-                false
-            } else {
-                tx.send(ClientMessage::from_bytes(&msg)).unwrap();
-
-                match rx.recv() {
-                    Err(_) | Ok(ServerMessage::EndConnection {}) => {
-                        println!("fermeture de la connexion @{}", get_peer_address(&socket));
-                        socket.shutdown(Shutdown::Both).unwrap_or(());
-                        tx.send(ClientMessage::ConnectionEnded {}).unwrap();
-                        false
-                    }
-                    Ok(msg) => match write_line(&mut socket, &msg.into_bytes()) {
-                        Ok(_) => true,
-                        Err(err) => {
-                            println!(
-                                "une erreur est survenue, fermeture de la connexion @{} ({})",
-                                get_peer_address(&socket),
-                                err,
-                            );
-                            socket.shutdown(Shutdown::Both).unwrap_or(());
-                            tx.send(ClientMessage::ConnectionEnded {}).unwrap();
-                            false
-                        }
-                    },
-                }
-            }
+    log::set_connection(Some(connection));
+    let address = get_peer_address(&socket);
+    let mut session = Session {
+        socket,
+        tx,
+        rx,
+        encoding: Encoding::new(),
+        reader: MessageReader::new(),
+        watchdog: Watchdog::new(limits),
+        transcript,
+        msg: Vec::new(),
+    };
+
+    match session.run() {
+        Ok(Ending::ClientLeft) => info!("le client @{} a mis fin à la connexion", address),
+        Ok(Ending::Closed) => info!("fermeture de la connexion @{}", address),
+        Ok(Ending::LimitExceeded(reason)) => {
+            warn!(
+                "déconnexion du client @{} : {}",
+                address,
+                Language::French.problem(&reason)
+            )
         }
-        Err(err) => match err.kind() {
-            ErrorKind::WouldBlock | ErrorKind::TimedOut => match rx.try_recv() {
-                Err(TryRecvError::Empty) => true,
-                Err(TryRecvError::Disconnected) | Ok(ServerMessage::EndConnection {}) => {
-                    println!("fermeture de la connexion @{}", get_peer_address(&socket));
-                    socket.shutdown(Shutdown::Both).unwrap_or(());
-                    tx.send(ClientMessage::ConnectionEnded {}).unwrap();
-                    false
+        Err(err @ Error::WriteTimeout) => warn!("déconnexion du client @{} : {}", address, err),
+        Err(err) => error!(
+            "une erreur est survenue, fermeture de la connexion @{} ({})",
+            address, err,
+        ),
+    }
+
+    session.socket.shutdown(Shutdown::Both).unwrap_or(());
+    // le jeu a pu s'arrêter avant la connexion, il n'y a alors plus personne à prévenir
+    session
+        .tx
+        .send(ClientMessage::ConnectionEnded)
+        .unwrap_or(());
+}
+
+impl Session {
+    fn run(&mut self) -> Result<Ending, Error> {
+        self.socket.set_nonblocking(false)?; // Windows crée des sockets non-bloquants par défaut
+        self.socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        self.socket
+            .set_write_timeout(Some(self.watchdog.limits().beat * WRITE_TIMEOUT_BEATS))?;
+
+        self.write(b"D\xc3\x89BUT\n", true)?;
+
+        loop {
+            match self
+                .reader
+                .read(&mut self.socket, self.encoding.framing, &mut self.msg)
+            {
+                Ok(Incoming::Closed) => {
+                    self.record(|t| t.note("connexion fermée par le client"));
+                    return Ok(Ending::ClientLeft);
                 }
-                Ok(msg) => match write_line(&mut socket, &msg.into_bytes()) {
-                    Ok(_) => true,
-                    Err(err) => {
-                        println!(
-                            "une erreur est survenue, fermeture de la connexion @{} ({})",
-                            get_peer_address(&socket),
-                            err,
+                Ok(incoming) => match self.watchdog.command_received() {
+                    Verdict::Accepted => {
+                        self.record_incoming(&incoming);
+                        let command = match incoming {
+                            Incoming::Oversized => {
+                                ClientMessage::ProtocolError(Problem::MessageTooLong)
+                            }
+                            _ => ClientMessage::from_bytes(&self.msg),
+                        };
+                        debug!(
+                            "reçu « {} » : {:?}",
+                            String::from_utf8_lossy(&self.msg),
+                            command
                         );
-                        socket.shutdown(Shutdown::Both).unwrap_or(());
-                        tx.send(ClientMessage::ConnectionEnded {}).unwrap();
-                        false
+                        self.tx.send(command)?;
+
+                        match self.rx.recv() {
+                            Err(_) | Ok(ServerMessage::EndConnection) => return Ok(Ending::Closed),
+                            Ok(msg) => self.send(msg)?,
+                        }
+                    }
+                    Verdict::Refused => {
+                        self.record_refused(&incoming);
+                        debug!("refusé « {} »", String::from_utf8_lossy(&self.msg));
+                        let refusal = ServerMessage::Error(Problem::TooManyCommands(
+                            self.watchdog.limits().commands_per_beat,
+                        ));
+                        self.send(refusal)?;
                     }
+                    Verdict::Exceeded(reason) => return self.disconnect(reason),
                 },
-            },
-            _ => {
-                println!(
-                    "une erreur est survenue, fermeture de la connexion @{} ({})",
-                    get_peer_address(&socket),
-                    err,
-                );
-                socket.shutdown(Shutdown::Both).unwrap_or(());
-                tx.send(ClientMessage::ConnectionEnded {}).unwrap();
-                false
-            }
-        },
-    } {}
-}
+                Err(err)
+                    if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
+                {
+                    if let Some(reason) = self.watchdog.check_timeouts() {
+                        return self.disconnect(reason);
+                    }
 
-fn read_line<R: Read>(stream: &mut R, output: &mut Vec<u8>) -> Result<isize, std::io::Error> {
-    let mut buffer = [0 as u8; 50];
-    output.clear();
-
-    'l: loop {
-        let size = stream.read(&mut buffer)?;
-
-        if size == 0 {
-            return Ok(-1);
-        } else {
-            output.extend_from_slice(&buffer[0..size]);
-            if buffer[size - 1] == 10 {
-                output.pop().unwrap();
-                if let Some(c) = output.last() {
-                    if *c == 13 {
-                        output.pop().unwrap();
+                    // envoyer les messages qui ne répondent pas directement à une commande
+                    // (comme les étapes d'une séquence)
+                    loop {
+                        match self.rx.try_recv() {
+                            Err(TryRecvError::Empty) => break,
+                            Err(TryRecvError::Disconnected) | Ok(ServerMessage::EndConnection) => {
+                                return Ok(Ending::Closed)
+                            }
+                            Ok(msg) => self.send(msg)?,
+                        }
                     }
                 }
-                break 'l;
+                Err(err) => return Err(err.into()),
             }
         }
     }
 
-    Ok(output.len().try_into().unwrap())
-}
+    // le message est d'abord encodé en mémoire pour pouvoir enregistrer les octets envoyés
+    fn send(&mut self, msg: ServerMessage) -> Result<(), Error> {
+        debug!("envoyé : {:?}", msg);
+        let lines = self.encoding.framing == Framing::Text;
+        let mut data = Vec::new();
+        write_message(&mut data, msg, &mut self.encoding)?;
+        self.write(&data, lines)
+    }
+
+    fn write(&mut self, data: &[u8], lines: bool) -> Result<(), Error> {
+        self.record(|t| t.sent(data, lines));
+        match self.socket.write_all(data) {
+            Err(err)
+                if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
+            {
+                Err(Error::WriteTimeout)
+            }
+            result => Ok(result?),
+        }
+    }
 
-fn write_line<W: Write>(stream: &mut W, input: &[u8]) -> Result<usize, std::io::Error> {
-    let msg_size = stream.write(input)?;
-    let lf_size = stream.write(&[10])?;
+    // le message sera écrit dans le transcript quand le jeu le traitera
+    fn record_incoming(&mut self, incoming: &Incoming) {
+        if let Some(transcript) = self.transcript.as_mut() {
+            match incoming {
+                Incoming::Oversized => transcript.received_oversized(),
+                _ => transcript.received(&self.msg),
+            }
+        }
+    }
 
-    Ok(msg_size + lf_size)
+    fn record_refused(&mut self, incoming: &Incoming) {
+        if let Some(transcript) = self.transcript.as_mut() {
+            let result = match incoming {
+                Incoming::Oversized => transcript.note("message trop long ignoré"),
+                _ => transcript.refused(&self.msg),
+            };
+            self.recorded(result);
+        }
+    }
+
+    fn record<F>(&mut self, write: F)
+    where
+        F: FnOnce(&mut Transcript) -> Result<(), std::io::Error>,
+    {
+        if let Some(transcript) = self.transcript.as_mut() {
+            let result = write(transcript);
+            self.recorded(result);
+        }
+    }
+
+    // une erreur d'écriture du transcript ne doit pas couper la connexion : il est abandonné
+    fn recorded(&mut self, result: Result<(), std::io::Error>) {
+        if let Err(err) = result {
+            warn!("arrêt de l'enregistrement de la connexion : {}", err);
+            self.transcript = None;
+        }
+    }
+
+    // prévient le client de la raison de la déconnexion avant de fermer la connexion
+    fn disconnect(&mut self, reason: Problem) -> Result<Ending, Error> {
+        self.send(ServerMessage::Bye(reason.clone()))?;
+        Ok(Ending::LimitExceeded(reason))
+    }
 }
 
 pub struct NetworkThreadBuilder {
@@ -136,9 +231,13 @@ impl NetworkThreadBuilder {
         }
     }
 
-    pub fn new_thread(&mut self) -> Builder {
+    // le numéro du thread sert aussi à identifier la connexion dans le journal
+    pub fn new_thread(&mut self) -> (u32, Builder) {
         self.last_discriminant += 1;
-        Builder::new().name(format!("network-{}", self.last_discriminant))
+        (
+            self.last_discriminant,
+            Builder::new().name(format!("network-{}", self.last_discriminant)),
+        )
     }
 }
 
@@ -148,6 +247,13 @@ pub struct ThreadHandles<S, R, T> {
     pub join_handle: JoinHandle<T>,
 }
 
+impl<S, R, T> ThreadHandles<S, R, T> {
+    // attend la fin du thread d'une connexion terminée
+    pub fn join(self) -> Result<T, Error> {
+        self.join_handle.join().map_err(|_| Error::ThreadPanicked)
+    }
+}
+
 fn get_peer_address(socket: &TcpStream) -> String {
     match socket.peer_addr() {
         Ok(addr) => addr.to_string(),
@@ -1,6 +1,7 @@
+use super::pack::{self, AssetPack};
 use sdl2::mixer::AUDIO_S16LSB;
 use sdl2::mixer::DEFAULT_CHANNELS;
-use sdl2::mixer::{open_audio, Music};
+use sdl2::mixer::{open_audio, Music, MAX_VOLUME};
 
 pub struct Sounds<'lt> {
     menu_music: Music<'lt>,
@@ -8,11 +9,20 @@ pub struct Sounds<'lt> {
 }
 
 impl Sounds<'_> {
-    pub fn load() -> Self {
+    pub fn load(pack: Option<&AssetPack>) -> Self {
         open_audio(44100, AUDIO_S16LSB, DEFAULT_CHANNELS, 512).unwrap();
+        let load = |name: &str, embedded: &'static [u8]| {
+            pack.and_then(|pack| {
+                let path = pack.path(name)?;
+                Music::from_file(path)
+                    .map_err(|err| pack.failed(name, err))
+                    .ok()
+            })
+            .unwrap_or_else(|| Music::from_static_bytes(embedded).unwrap())
+        };
         Self {
-            menu_music: Music::from_static_bytes(include_bytes!("../../res/menu.mp3")).unwrap(),
-            in_game_music: Music::from_static_bytes(include_bytes!("../../res/level.mp3")).unwrap(),
+            menu_music: load(pack::MENU_MUSIC, include_bytes!("../../res/menu.mp3")),
+            in_game_music: load(pack::LEVEL_MUSIC, include_bytes!("../../res/level.mp3")),
         }
     }
 
@@ -23,4 +33,9 @@ impl Sounds<'_> {
     pub fn play_in_game_music(&self) {
         self.in_game_music.play(-1).unwrap();
     }
+
+    // volume en pourcentage, appliqué à toutes les musiques
+    pub fn set_volume(&self, percent: u32) {
+        Music::set_volume(MAX_VOLUME * percent as i32 / 100);
+    }
 }
@@ -1,170 +1,137 @@
+use super::pack::AssetPack;
+use super::sheet::{Animation, Sheet};
 use sdl2::pixels::PixelFormatEnum;
-use sdl2::render::{Texture, TextureCreator};
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget, Texture, TextureCreator};
 use sdl2::surface::Surface;
+use std::collections::HashMap;
 
+// octets R, G, B, A dans cet ordre, comme les images décodées
+const FORMAT: PixelFormatEnum = PixelFormatEnum::ABGR8888;
+
+// images nommées, chacune étant une zone d'une des textures chargées
 pub struct Images<'lt> {
-    background: Texture<'lt>,
-    border: Texture<'lt>,
-    cadence_1: Texture<'lt>,
-    cadence_2: Texture<'lt>,
-    cadence_3: Texture<'lt>,
-    cadence_4: Texture<'lt>,
-    diamond: Texture<'lt>,
-    diamond_icon: Texture<'lt>,
-    exit: Texture<'lt>,
-    exit_locked: Texture<'lt>,
-    floor_green: Texture<'lt>,
-    floor_purple: Texture<'lt>,
-    shovel: Texture<'lt>,
-    stone: Texture<'lt>,
-    wall: Texture<'lt>,
+    textures: Vec<Texture<'lt>>,
+    frames: HashMap<String, (usize, Rect)>,
+    animations: HashMap<String, Animation>,
 }
 
 impl<'lt> Images<'lt> {
-    pub fn load<T>(tc: &'lt TextureCreator<T>) -> Self {
-        let mut background_data = include_raw_image!("res/background.png");
-        let mut border_data = include_raw_image!("res/border.png");
-        let mut cadence_1_data = include_raw_image!("res/cadence_1.png");
-        let mut cadence_2_data = include_raw_image!("res/cadence_2.png");
-        let mut cadence_3_data = include_raw_image!("res/cadence_3.png");
-        let mut cadence_4_data = include_raw_image!("res/cadence_4.png");
-        let mut diamond_data = include_raw_image!("res/diamond.png");
-        let mut diamond_icon_data = include_raw_image!("res/diamond_icon.png");
-        let mut exit_data = include_raw_image!("res/exit.png");
-        let mut exit_locked_data = include_raw_image!("res/exit_locked.png");
-        let mut floor_green_data = include_raw_image!("res/floor_green.png");
-        let mut floor_purple_data = include_raw_image!("res/floor_purple.png");
-        let mut shovel_data = include_raw_image!("res/shovel.png");
-        let mut stone_data = include_raw_image!("res/stone.png");
-        let mut wall_data = include_raw_image!("res/wall.png");
-
-        let background_surface = Surface::from_data(
-            &mut background_data,
-            480,
-            270,
-            1920,
-            PixelFormatEnum::ABGR8888,
-        )
-        .unwrap();
-        let border_surface =
-            Surface::from_data(&mut border_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let cadence_1_surface =
-            Surface::from_data(&mut cadence_1_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let cadence_2_surface =
-            Surface::from_data(&mut cadence_2_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let cadence_3_surface =
-            Surface::from_data(&mut cadence_3_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let cadence_4_surface =
-            Surface::from_data(&mut cadence_4_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let diamond_surface =
-            Surface::from_data(&mut diamond_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let diamond_icon_surface = Surface::from_data(
-            &mut diamond_icon_data,
-            17,
-            13,
-            68,
-            PixelFormatEnum::ABGR8888,
-        )
-        .unwrap();
-        let exit_surface =
-            Surface::from_data(&mut exit_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let exit_locked_surface =
-            Surface::from_data(&mut exit_locked_data, 24, 48, 96, PixelFormatEnum::ABGR8888)
-                .unwrap();
-        let floor_green_surface =
-            Surface::from_data(&mut floor_green_data, 24, 48, 96, PixelFormatEnum::ABGR8888)
-                .unwrap();
-        let floor_purple_surface = Surface::from_data(
-            &mut floor_purple_data,
-            24,
-            48,
-            96,
-            PixelFormatEnum::ABGR8888,
-        )
-        .unwrap();
-        let shovel_surface =
-            Surface::from_data(&mut shovel_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let stone_surface =
-            Surface::from_data(&mut stone_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-        let wall_surface =
-            Surface::from_data(&mut wall_data, 24, 48, 96, PixelFormatEnum::ABGR8888).unwrap();
-
-        Self {
-            background: background_surface.as_texture(&tc).unwrap(),
-            border: border_surface.as_texture(&tc).unwrap(),
-            cadence_1: cadence_1_surface.as_texture(&tc).unwrap(),
-            cadence_2: cadence_2_surface.as_texture(&tc).unwrap(),
-            cadence_3: cadence_3_surface.as_texture(&tc).unwrap(),
-            cadence_4: cadence_4_surface.as_texture(&tc).unwrap(),
-            diamond: diamond_surface.as_texture(&tc).unwrap(),
-            diamond_icon: diamond_icon_surface.as_texture(&tc).unwrap(),
-            exit: exit_surface.as_texture(&tc).unwrap(),
-            exit_locked: exit_locked_surface.as_texture(&tc).unwrap(),
-            floor_green: floor_green_surface.as_texture(&tc).unwrap(),
-            floor_purple: floor_purple_surface.as_texture(&tc).unwrap(),
-            shovel: shovel_surface.as_texture(&tc).unwrap(),
-            stone: stone_surface.as_texture(&tc).unwrap(),
-            wall: wall_surface.as_texture(&tc).unwrap(),
+    // la planche intégrée au jeu est chargée en premier : la planche et les images d'un
+    // pack remplacent ensuite celles du même nom
+    pub fn load<T>(tc: &'lt TextureCreator<T>, pack: Option<&AssetPack>) -> Self {
+        let mut images = Self {
+            textures: Vec::new(),
+            frames: HashMap::new(),
+            animations: HashMap::new(),
+        };
+
+        let sheet = Sheet::parse(include_str!("../../res/sprites.cfg"), "res/sprites.cfg");
+        // les images intégrées sont décodées à la compilation, avec leur taille
+        let (width, height, mut data) = include_raw_image!("res/sprites.png");
+        let texture = create_texture(tc, &mut data, width, height).unwrap();
+        images.add_sheet(texture, sheet);
+        let (width, height, mut data) = include_raw_image!("res/background.png");
+        let background = create_texture(tc, &mut data, width, height).unwrap();
+        images.add_image("background", background);
+
+        if let Some(pack) = pack {
+            if let Some((sheet, mut image)) = pack.sheet() {
+                let (width, height) = image.dimensions();
+                match create_texture(tc, &mut image, width, height) {
+                    Ok(texture) => images.add_sheet(texture, sheet),
+                    Err(err) => pack.failed(super::pack::SHEET, err),
+                }
+            }
+            for name in pack.images() {
+                if !images.frames.contains_key(name) {
+                    pack.failed(name, "aucune image de ce nom".to_string());
+                    continue;
+                }
+                if let Some(mut image) = pack.image(name) {
+                    let (width, height) = image.dimensions();
+                    match create_texture(tc, &mut image, width, height) {
+                        Ok(texture) => images.add_image(name, texture),
+                        Err(err) => pack.failed(name, err),
+                    }
+                }
+            }
         }
+        images
     }
 
-    pub fn background(&self) -> &Texture<'lt> {
-        &self.background
-    }
-
-    pub fn border(&self) -> &Texture<'lt> {
-        &self.border
-    }
-
-    pub fn cadence_1(&self) -> &Texture<'lt> {
-        &self.cadence_1
-    }
-
-    pub fn cadence_2(&self) -> &Texture<'lt> {
-        &self.cadence_2
-    }
-
-    pub fn cadence_3(&self) -> &Texture<'lt> {
-        &self.cadence_3
-    }
-
-    pub fn cadence_4(&self) -> &Texture<'lt> {
-        &self.cadence_4
-    }
-
-    pub fn diamond(&self) -> &Texture<'lt> {
-        &self.diamond
-    }
-
-    pub fn diamond_icon(&self) -> &Texture<'lt> {
-        &self.diamond_icon
-    }
-
-    pub fn exit(&self) -> &Texture<'lt> {
-        &self.exit
-    }
-
-    pub fn exit_locked(&self) -> &Texture<'lt> {
-        &self.exit_locked
-    }
-
-    pub fn floor_green(&self) -> &Texture<'lt> {
-        &self.floor_green
-    }
-
-    pub fn floor_purple(&self) -> &Texture<'lt> {
-        &self.floor_purple
+    fn add_sheet(&mut self, texture: Texture<'lt>, sheet: Sheet) {
+        self.textures.push(texture);
+        let index = self.textures.len() - 1;
+        for (name, area) in sheet.frames {
+            self.frames.insert(name, (index, area));
+        }
+        self.animations.extend(sheet.animations);
+    }
+
+    // une image seule occupe toute sa texture
+    fn add_image(&mut self, name: &str, texture: Texture<'lt>) {
+        let query = texture.query();
+        self.textures.push(texture);
+        self.frames.insert(
+            name.to_string(),
+            (
+                self.textures.len() - 1,
+                Rect::new(0, 0, query.width, query.height),
+            ),
+        );
+    }
+
+    // une image inconnue n'est pas dessinée
+    pub fn draw<C>(&self, canvas: &mut Canvas<C>, name: &str, dest: impl Into<Option<Rect>>)
+    where
+        C: RenderTarget,
+    {
+        if let Some((texture, area)) = self.frames.get(name) {
+            canvas.copy(&self.textures[*texture], *area, dest).unwrap();
+        } else {
+            debug!("image « {} » introuvable", name);
+        }
     }
 
-    pub fn shovel(&self) -> &Texture<'lt> {
-        &self.shovel
+    pub fn draw_flipped<C>(&self, canvas: &mut Canvas<C>, name: &str, dest: Rect, flip: bool)
+    where
+        C: RenderTarget,
+    {
+        if let Some((texture, area)) = self.frames.get(name) {
+            canvas
+                .copy_ex(
+                    &self.textures[*texture],
+                    *area,
+                    dest,
+                    0.0,
+                    None,
+                    flip,
+                    false,
+                )
+                .unwrap();
+        } else {
+            debug!("image « {} » introuvable", name);
+        }
     }
 
-    pub fn stone(&self) -> &Texture<'lt> {
-        &self.stone
+    // image d'une animation ticks après son début ; sans animation de ce nom,
+    // l'image du même nom est affichée telle quelle
+    pub fn animation_frame<'a>(&'a self, animation: &'a str, ticks: u32) -> &'a str {
+        match self.animations.get(animation) {
+            Some(animation) => animation.frame_at(ticks),
+            None => animation,
+        }
     }
+}
 
-    pub fn wall(&self) -> &Texture<'lt> {
-        &self.wall
-    }
+fn create_texture<'lt, T>(
+    tc: &'lt TextureCreator<T>,
+    data: &mut [u8],
+    width: u32,
+    height: u32,
+) -> Result<Texture<'lt>, String> {
+    Surface::from_data(data, width, height, width * 4, FORMAT)?
+        .as_texture(tc)
+        .map_err(|err| err.to_string())
 }
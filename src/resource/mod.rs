@@ -1,3 +1,5 @@
 pub mod audio;
 pub mod image;
+pub mod pack;
+pub mod sheet;
 pub mod text;
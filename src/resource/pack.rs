@@ -0,0 +1,217 @@
+use super::sheet::Sheet;
+use crate::args::option;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// fichier décrivant un pack, à la racine de son dossier
+const MANIFEST: &str = "pack.cfg";
+
+// ressources autres que les images ; les ressources absentes du pack gardent leur version
+// intégrée au jeu
+pub const FONT: &str = "font";
+pub const MENU_MUSIC: &str = "menu_music";
+pub const LEVEL_MUSIC: &str = "level_music";
+pub const SHEET: &str = "sheet";
+
+// pack de ressources chargé au lancement : un dossier contenant pack.cfg, dont chaque
+// ligne « nom = fichier » remplace une ressource par un fichier du dossier, par exemple
+//   wall = murs/brique.png
+//   font = police.ttf
+//   level_music = niveau.ogg
+//   sheet = lutins.cfg
+// tout autre nom est celui d'une image de la planche (voir res/sprites.cfg), remplacée par
+// un PNG de n'importe quelle taille ; sheet ajoute une planche dont les images et les
+// animations remplacent celles du même nom. La musique peut être de tout format lu par
+// SDL_mixer.
+pub struct AssetPack {
+    directory: PathBuf,
+    files: HashMap<String, PathBuf>,
+}
+
+impl AssetPack {
+    // option reconnue : --assets <dossier>
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let directory = option(args, "--assets")?;
+        match Self::load(Path::new(directory)) {
+            Ok(pack) => Some(pack),
+            Err(err) => {
+                warn!(
+                    "pack de ressources {} ignoré, {} n'a pas pu être lu : {}",
+                    directory, MANIFEST, err
+                );
+                None
+            }
+        }
+    }
+
+    // les lignes invalides sont ignorées
+    pub fn load(directory: &Path) -> Result<Self, std::io::Error> {
+        let content = std::fs::read_to_string(directory.join(MANIFEST))?;
+        let mut files = HashMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((name, file)) if !name.trim().is_empty() && !file.trim().is_empty() => {
+                    files.insert(name.trim().to_string(), directory.join(file.trim()));
+                }
+                _ => warn!("ligne « {} » de {} ignorée", line, MANIFEST),
+            }
+        }
+        info!(
+            "pack de ressources {} : {} ressource(s) remplacée(s)",
+            directory.display(),
+            files.len()
+        );
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            files,
+        })
+    }
+
+    pub fn path(&self, name: &str) -> Option<&Path> {
+        self.files.get(name).map(|path| path.as_path())
+    }
+
+    // None si la ressource n'est pas remplacée ou si son fichier n'a pas pu être lu
+    pub fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let path = self.path(name)?;
+        std::fs::read(path)
+            .map_err(|err| self.failed(name, err.to_string()))
+            .ok()
+    }
+
+    // images de la planche remplacées une par une
+    pub fn images(&self) -> impl Iterator<Item = &str> {
+        self.files
+            .keys()
+            .map(|name| name.as_str())
+            .filter(|name| ![FONT, MENU_MUSIC, LEVEL_MUSIC, SHEET].contains(name))
+    }
+
+    // image décodée en RGBA, 4 octets par pixel
+    pub fn image(&self, name: &str) -> Option<image::RgbaImage> {
+        let path = self.path(name)?.to_path_buf();
+        self.decode(name, &path)
+    }
+
+    // planche du pack et son image, indiquée par rapport au fichier de la planche
+    pub fn sheet(&self) -> Option<(Sheet, image::RgbaImage)> {
+        let path = self.path(SHEET)?.to_path_buf();
+        let content = String::from_utf8(self.read(SHEET)?)
+            .map_err(|err| self.failed(SHEET, err.to_string()))
+            .ok()?;
+        let sheet = Sheet::parse(&content, &path.display().to_string());
+        let image = match &sheet.image {
+            Some(file) => {
+                self.decode(SHEET, &path.parent().unwrap_or(&self.directory).join(file))?
+            }
+            None => {
+                self.failed(SHEET, "la planche n'indique pas son image".to_string());
+                return None;
+            }
+        };
+        Some((sheet, image))
+    }
+
+    fn decode(&self, name: &str, path: &Path) -> Option<image::RgbaImage> {
+        let data = std::fs::read(path)
+            .map_err(|err| self.failed(name, err.to_string()))
+            .ok()?;
+        image::load_from_memory(&data)
+            .map(|image| image.into_rgba8())
+            .map_err(|err| self.failed(name, err.to_string()))
+            .ok()
+    }
+
+    // le jeu garde alors la ressource intégrée
+    pub fn failed(&self, name: &str, err: String) {
+        warn!(
+            "ressource « {} » du pack {} ignorée : {}",
+            name,
+            self.directory.display(),
+            err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // dossier propre à chaque test, dans le dossier temporaire
+    fn pack_directory(name: &str, manifest: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("rustdancer-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join(MANIFEST), manifest).unwrap();
+        directory
+    }
+
+    #[test]
+    fn manifest_lines_replace_resources() {
+        let directory = pack_directory(
+            "pack",
+            "# pack de test\n\
+             \n\
+             wall = murs/brique.png\n\
+             \x20 font=police.ttf \n\
+             level_music = niveau.ogg\n\
+             sheet = lutins.cfg\n\
+             player_0 = joueur.png\n",
+        );
+        let pack = AssetPack::load(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            pack.path("wall"),
+            Some(directory.join("murs/brique.png").as_path())
+        );
+        assert_eq!(
+            pack.path(FONT),
+            Some(directory.join("police.ttf").as_path())
+        );
+        assert_eq!(pack.path(MENU_MUSIC), None);
+
+        let mut images: Vec<&str> = pack.images().collect();
+        images.sort();
+        assert_eq!(images, ["player_0", "wall"]);
+    }
+
+    #[test]
+    fn invalid_lines_are_ignored() {
+        let directory = pack_directory(
+            "invalid",
+            "sans égal\n\
+             = orphelin.png\n\
+             wall =\n\
+             floor = sol.png\n",
+        );
+        let pack = AssetPack::load(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(pack.images().collect::<Vec<_>>(), ["floor"]);
+        assert_eq!(pack.path("wall"), None);
+    }
+
+    #[test]
+    fn missing_manifest_is_an_error() {
+        let directory = pack_directory("empty", "");
+        std::fs::remove_file(directory.join(MANIFEST)).unwrap();
+        let result = AssetPack::load(&directory);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn unreadable_file_keeps_the_builtin_resource() {
+        let directory = pack_directory("unreadable", "font = absente.ttf\n");
+        let pack = AssetPack::load(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(pack.read(FONT).is_none());
+        assert!(pack.image("wall").is_none());
+    }
+}
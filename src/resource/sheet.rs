@@ -0,0 +1,261 @@
+use sdl2::rect::Rect;
+use std::str::FromStr;
+
+// façon de continuer une animation une fois sa dernière image affichée
+#[derive(Copy, Clone, PartialEq)]
+pub enum LoopMode {
+    // recommence depuis la première image
+    Loop,
+    // reste sur la dernière image
+    Once,
+    // repart en arrière jusqu'à la première image, puis recommence
+    PingPong,
+}
+
+// suite d'images d'une planche, chacune affichée un certain nombre de ticks
+// (il y a quatre ticks par temps, les animations suivent donc la musique)
+pub struct Animation {
+    frames: Vec<(String, u32)>,
+    mode: LoopMode,
+}
+
+impl Animation {
+    // image à afficher ticks après le début de l'animation
+    pub fn frame_at(&self, ticks: u32) -> &str {
+        let forward = total(&self.frames);
+        let ticks = match self.mode {
+            LoopMode::Loop => ticks % forward,
+            LoopMode::Once => ticks.min(forward - 1),
+            LoopMode::PingPong => {
+                // au retour, ni la dernière ni la première image ne sont répétées
+                let middle = if self.frames.len() > 2 {
+                    &self.frames[1..self.frames.len() - 1]
+                } else {
+                    &[]
+                };
+                let ticks = ticks % (forward + total(middle));
+                if ticks >= forward {
+                    return find(middle.iter().rev(), ticks - forward);
+                }
+                ticks
+            }
+        };
+        find(self.frames.iter(), ticks)
+    }
+}
+
+fn total(frames: &[(String, u32)]) -> u32 {
+    frames.iter().map(|(_, duration)| duration).sum()
+}
+
+// image affichée ticks après le début de frames ; la dernière si ticks dépasse leur durée
+fn find<'a>(frames: impl Iterator<Item = &'a (String, u32)>, ticks: u32) -> &'a str {
+    let mut remaining = ticks;
+    let mut last = "";
+    for (name, duration) in frames {
+        if remaining < *duration {
+            return name;
+        }
+        remaining -= duration;
+        last = name;
+    }
+    last
+}
+
+// description d'une planche de lutins, sur le modèle de rustdancer.cfg :
+//   image = planche.png
+//   frame <nom> = <x> <y> <largeur> <hauteur>
+//   animation <nom> = <loop|once|pingpong> <image>[:<ticks>] ...
+// la durée d'une image vaut un tick si elle n'est pas précisée
+pub struct Sheet {
+    pub image: Option<String>,
+    pub frames: Vec<(String, Rect)>,
+    pub animations: Vec<(String, Animation)>,
+}
+
+impl Sheet {
+    // les lignes invalides sont ignorées ; source sert seulement aux messages
+    pub fn parse(content: &str, source: &str) -> Self {
+        let mut sheet = Self {
+            image: None,
+            frames: Vec::new(),
+            animations: Vec::new(),
+        };
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line
+                .split_once('=')
+                .map(|(key, value)| sheet.add(key.trim(), value.trim()));
+            if parsed != Some(true) {
+                warn!("ligne « {} » de {} ignorée", line, source);
+            }
+        }
+        sheet
+    }
+
+    fn add(&mut self, key: &str, value: &str) -> bool {
+        let key: Vec<&str> = key.split_whitespace().collect();
+        match key.as_slice() {
+            ["image"] => self.image = Some(value.to_string()),
+            ["frame", name] => match parse_rect(value) {
+                Some(area) => self.frames.push((name.to_string(), area)),
+                None => return false,
+            },
+            ["animation", name] => match parse_animation(value) {
+                Some(animation) => self.animations.push((name.to_string(), animation)),
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn parse_rect(value: &str) -> Option<Rect> {
+    let numbers: Vec<u32> = value
+        .split_whitespace()
+        .map(u32::from_str)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match numbers.as_slice() {
+        [x, y, width, height] if *width > 0 && *height > 0 => {
+            Some(Rect::new(*x as i32, *y as i32, *width, *height))
+        }
+        _ => None,
+    }
+}
+
+fn parse_animation(value: &str) -> Option<Animation> {
+    let mut words = value.split_whitespace();
+    let mode = match words.next()? {
+        "loop" => LoopMode::Loop,
+        "once" => LoopMode::Once,
+        "pingpong" => LoopMode::PingPong,
+        _ => return None,
+    };
+    let frames: Vec<(String, u32)> = words
+        .map(|word| match word.split_once(':') {
+            Some((name, ticks)) => match u32::from_str(ticks) {
+                Ok(ticks) if ticks > 0 => Some((name.to_string(), ticks)),
+                _ => None,
+            },
+            None => Some((word.to_string(), 1)),
+        })
+        .collect::<Option<_>>()?;
+    if frames.is_empty() {
+        return None;
+    }
+    Some(Animation { frames, mode })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_animation(mode: LoopMode, frames: &[(&str, u32)]) -> Animation {
+        Animation {
+            frames: frames
+                .iter()
+                .map(|(name, ticks)| (name.to_string(), *ticks))
+                .collect(),
+            mode,
+        }
+    }
+
+    fn frames_from(animation: &Animation, ticks: u32) -> Vec<&str> {
+        (0..ticks).map(|tick| animation.frame_at(tick)).collect()
+    }
+
+    #[test]
+    fn valid_lines_are_parsed() {
+        let sheet = Sheet::parse(
+            "# planche de test\n\
+             \n\
+             image = lutins.png\n\
+             frame wall = 0 16 16 24\n\
+             frame  player_0 =  16 0 16 16 \n\
+             animation player = pingpong player_0:2 player_1 player_2:3\n",
+            "test",
+        );
+        assert_eq!(sheet.image.as_deref(), Some("lutins.png"));
+        assert_eq!(
+            sheet.frames,
+            vec![
+                ("wall".to_string(), Rect::new(0, 16, 16, 24)),
+                ("player_0".to_string(), Rect::new(16, 0, 16, 16)),
+            ]
+        );
+        assert_eq!(sheet.animations.len(), 1);
+        let (name, animation) = &sheet.animations[0];
+        assert_eq!(name, "player");
+        assert!(animation.mode == LoopMode::PingPong);
+        assert_eq!(
+            animation.frames,
+            vec![
+                ("player_0".to_string(), 2),
+                ("player_1".to_string(), 1),
+                ("player_2".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_lines_are_ignored() {
+        let sheet = Sheet::parse(
+            "frame = 0 0 16 16\n\
+             frame a b = 0 0 16 16\n\
+             frame empty = 0 0 0 16\n\
+             frame short = 0 0 16\n\
+             frame long = 0 0 16 16 16\n\
+             frame negative = -1 0 16 16\n\
+             frame word = 0 0 seize 16\n\
+             animation mode = bounce a b\n\
+             animation none = loop\n\
+             animation zero = loop a:0 b\n\
+             animation bad = once a:x\n\
+             sprite wall = 0 0 16 16\n\
+             sans égal\n\
+             frame ok = 1 2 3 4\n",
+            "test",
+        );
+        assert_eq!(sheet.image, None);
+        assert_eq!(
+            sheet.frames,
+            vec![("ok".to_string(), Rect::new(1, 2, 3, 4))]
+        );
+        assert!(sheet.animations.is_empty());
+    }
+
+    #[test]
+    fn loop_starts_again() {
+        let animation = new_animation(LoopMode::Loop, &[("a", 2), ("b", 1)]);
+        assert_eq!(
+            frames_from(&animation, 7),
+            ["a", "a", "b", "a", "a", "b", "a"]
+        );
+    }
+
+    #[test]
+    fn once_stays_on_the_last_frame() {
+        let animation = new_animation(LoopMode::Once, &[("a", 1), ("b", 2)]);
+        assert_eq!(frames_from(&animation, 5), ["a", "b", "b", "b", "b"]);
+        assert_eq!(animation.frame_at(u32::MAX), "b");
+    }
+
+    #[test]
+    fn pingpong_goes_back_without_repeating_the_ends() {
+        let animation = new_animation(LoopMode::PingPong, &[("a", 1), ("b", 2), ("c", 1)]);
+        assert_eq!(
+            frames_from(&animation, 8),
+            ["a", "b", "b", "c", "b", "b", "a", "b"]
+        );
+
+        // avec deux images, il n'y a pas d'images intermédiaires à repasser
+        let animation = new_animation(LoopMode::PingPong, &[("a", 1), ("b", 1)]);
+        assert_eq!(frames_from(&animation, 4), ["a", "b", "a", "b"]);
+    }
+}
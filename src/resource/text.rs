@@ -1,32 +1,189 @@
 use sdl2::pixels::Color;
-use sdl2::render::Texture;
-use sdl2::render::TextureCreator;
-use sdl2::ttf::Font;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget, Texture, TextureCreator};
+use sdl2::rwops::RWops;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
 
+// épaisseur du contour des textes Outlined
+const OUTLINE: u16 = 2;
+// un texte qui n'a pas été affiché depuis ce nombre d'images est oublié
+const KEEP_FRAMES: u64 = 100;
+// au-delà, les textes les plus anciens sont oubliés même s'ils sont récents
+const CAPACITY: usize = 256;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TextRenderingFormat {
     Shaded(Color, Color),
     Blended(Color),
+    // texte de la première couleur entouré de la seconde, lisible sur n'importe quel fond
+    Outlined(Color, Color),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FontSize {
+    Small,
+    Normal,
+    Large,
+}
+
+impl FontSize {
+    const ALL: [FontSize; 3] = [FontSize::Small, FontSize::Normal, FontSize::Large];
+
+    fn points(self) -> u16 {
+        match self {
+            FontSize::Small => 14,
+            FontSize::Normal => 20,
+            FontSize::Large => 32,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TextStyle {
+    pub size: FontSize,
+    pub format: TextRenderingFormat,
+}
+
+impl TextStyle {
+    pub fn sized(self, size: FontSize) -> Self {
+        Self { size, ..self }
+    }
+}
+
+impl From<TextRenderingFormat> for TextStyle {
+    fn from(format: TextRenderingFormat) -> Self {
+        Self {
+            size: FontSize::Normal,
+            format,
+        }
+    }
+}
+
+// point du texte placé à la position demandée
+#[derive(Copy, Clone)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Center,
+    BottomLeft,
+    Bottom,
+}
+
+impl Anchor {
+    pub fn place(self, point: (i32, i32), width: u32, height: u32) -> Rect {
+        let (w, h) = (width as i32, height as i32);
+        let x = match self {
+            Anchor::TopLeft | Anchor::BottomLeft => point.0,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => point.0 - w / 2,
+            Anchor::TopRight => point.0 - w,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => point.1,
+            Anchor::Center => point.1 - h / 2,
+            Anchor::BottomLeft | Anchor::Bottom => point.1 - h,
+        };
+        Rect::new(x, y, width, height)
+    }
+}
+
+struct Fonts<'lt> {
+    regular: Font<'lt, 'static>,
+    outlined: Font<'lt, 'static>,
 }
 
+struct Entry<'lt> {
+    text: Rc<RenderedTextResult<'lt>>,
+    last_used: u64,
+}
+
+// les textes rendus sont gardés d'une image à l'autre : la plupart ne changent pas
+// le cache est rangé par style puis par texte, pour qu'un texte déjà rendu soit retrouvé
+// sans copier la chaîne ; elle n'est copiée qu'à la première utilisation
 pub struct TextRenderer<'lt, T> {
-    font: Font<'lt, 'lt>,
+    fonts: HashMap<FontSize, Fonts<'lt>>,
     texture_creator: &'lt TextureCreator<T>,
+    cache: RefCell<HashMap<TextStyle, HashMap<String, Entry<'lt>>>>,
+    frame: Cell<u64>,
 }
 
 impl<'lt, T> TextRenderer<'lt, T> {
-    pub fn new(font: Font<'lt, 'lt>, texture_creator: &'lt TextureCreator<T>) -> Self {
-        Self {
-            font,
+    pub fn new(
+        ttf_context: &'lt Sdl2TtfContext,
+        font_data: &'static [u8],
+        texture_creator: &'lt TextureCreator<T>,
+    ) -> Result<Self, String> {
+        let load = |points| ttf_context.load_font_from_rwops(RWops::from_bytes(font_data)?, points);
+        let mut fonts = HashMap::new();
+        for size in FontSize::ALL {
+            let mut outlined = load(size.points())?;
+            outlined.set_outline_width(OUTLINE);
+            fonts.insert(
+                size,
+                Fonts {
+                    regular: load(size.points())?,
+                    outlined,
+                },
+            );
+        }
+
+        Ok(Self {
+            fonts,
             texture_creator,
+            cache: RefCell::new(HashMap::new()),
+            frame: Cell::new(0),
+        })
+    }
+
+    pub fn render(&self, text: &str, style: impl Into<TextStyle>) -> Rc<RenderedTextResult<'lt>> {
+        let style = style.into();
+        let frame = self.frame.get();
+        let mut cache = self.cache.borrow_mut();
+        let texts = cache.entry(style).or_default();
+        if let Some(entry) = texts.get_mut(text) {
+            entry.last_used = frame;
+            return entry.text.clone();
         }
+
+        let rendered = Rc::new(self.render_uncached(text, style));
+        texts.insert(
+            text.to_string(),
+            Entry {
+                text: rendered.clone(),
+                last_used: frame,
+            },
+        );
+        rendered
     }
 
-    pub fn render(&self, text: &str, format: TextRenderingFormat) -> RenderedTextResult {
-        let partial = self.font.render(text);
+    fn render_uncached(&self, text: &str, style: TextStyle) -> RenderedTextResult<'lt> {
+        let fonts = &self.fonts[&style.size];
+        // SDL_ttf refuse les textes vides
+        let text = if text.is_empty() { " " } else { text };
 
-        let surface = match format {
-            TextRenderingFormat::Shaded(fg, bg) => partial.shaded(fg, bg),
-            TextRenderingFormat::Blended(fg) => partial.blended(fg),
+        let surface = match style.format {
+            TextRenderingFormat::Shaded(fg, bg) => fonts.regular.render(text).shaded(fg, bg),
+            TextRenderingFormat::Blended(fg) => fonts.regular.render(text).blended(fg),
+            TextRenderingFormat::Outlined(fg, outline) => fonts
+                .outlined
+                .render(text)
+                .blended(outline)
+                .and_then(|mut back| {
+                    let front = fonts.regular.render(text).blended(fg)?;
+                    let position = Rect::new(
+                        OUTLINE as i32,
+                        OUTLINE as i32,
+                        front.width(),
+                        front.height(),
+                    );
+                    front
+                        .blit(None, &mut back, position)
+                        .map_err(sdl2::ttf::FontError::SdlError)?;
+                    Ok(back)
+                }),
         }
         .unwrap();
 
@@ -39,6 +196,130 @@ impl<'lt, T> TextRenderer<'lt, T> {
             h: surface.height(),
         }
     }
+
+    // dessine un texte dont le point anchor est placé en point ; renvoie la zone occupée
+    pub fn draw<C>(
+        &self,
+        canvas: &mut Canvas<C>,
+        text: &str,
+        style: impl Into<TextStyle>,
+        point: (i32, i32),
+        anchor: Anchor,
+    ) -> Rect
+    where
+        C: RenderTarget,
+    {
+        let rendered = self.render(text, style);
+        let area = anchor.place(point, rendered.width(), rendered.height());
+        canvas.copy(rendered.texture(), None, area).unwrap();
+        area
+    }
+
+    // comme draw, en coupant le texte entre les mots pour ne pas dépasser max_width ;
+    // les lignes sont alignées à gauche, au centre ou à droite suivant anchor
+    pub fn draw_wrapped<C>(
+        &self,
+        canvas: &mut Canvas<C>,
+        text: &str,
+        style: impl Into<TextStyle>,
+        point: (i32, i32),
+        anchor: Anchor,
+        max_width: u32,
+    ) -> Rect
+    where
+        C: RenderTarget,
+    {
+        let style = style.into();
+        let lines: Vec<_> = self
+            .wrap(text, style, max_width)
+            .iter()
+            .map(|line| self.render(line, style))
+            .collect();
+        let width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+        let height = lines.iter().map(|line| line.height()).sum();
+        let area = anchor.place(point, width, height);
+
+        let mut y = area.y();
+        for line in lines {
+            let x = match anchor {
+                Anchor::TopLeft | Anchor::BottomLeft => area.x(),
+                Anchor::Top | Anchor::Center | Anchor::Bottom => {
+                    area.x() + (width - line.width()) as i32 / 2
+                }
+                Anchor::TopRight => area.right() - line.width() as i32,
+            };
+            canvas
+                .copy(
+                    line.texture(),
+                    None,
+                    Rect::new(x, y, line.width(), line.height()),
+                )
+                .unwrap();
+            y += line.height() as i32;
+        }
+        area
+    }
+
+    fn wrap(&self, text: &str, style: TextStyle, max_width: u32) -> Vec<String> {
+        let font = &self.fonts[&style.size].regular;
+        let margin = match style.format {
+            TextRenderingFormat::Outlined(..) => 2 * OUTLINE as u32,
+            _ => 0,
+        };
+        let fits = |line: &str| {
+            font.size_of(line)
+                .map_or(true, |(width, _)| width + margin <= max_width)
+        };
+
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            // un mot trop long pour tenir seul reste sur sa propre ligne
+            if fits(&candidate) || line.is_empty() {
+                line = candidate;
+            } else {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            }
+        }
+        lines.push(line);
+        lines
+    }
+
+    // appelé après chaque image : oublie les textes qui ne sont plus affichés
+    pub fn end_frame(&self) {
+        let frame = self.frame.get() + 1;
+        self.frame.set(frame);
+
+        let mut cache = self.cache.borrow_mut();
+        forget(&mut cache, |entry| entry.last_used + KEEP_FRAMES >= frame);
+        let count: usize = cache.values().map(|texts| texts.len()).sum();
+        if count > CAPACITY {
+            let mut ages: Vec<u64> = cache
+                .values()
+                .flat_map(|texts| texts.values())
+                .map(|entry| entry.last_used)
+                .collect();
+            ages.sort_unstable();
+            let oldest_kept = ages[ages.len() - CAPACITY];
+            forget(&mut cache, |entry| entry.last_used >= oldest_kept);
+        }
+    }
+}
+
+// ne garde que les textes qui vérifient keep, et les styles qui en ont encore
+fn forget<'lt>(
+    cache: &mut HashMap<TextStyle, HashMap<String, Entry<'lt>>>,
+    keep: impl Fn(&Entry<'lt>) -> bool,
+) {
+    cache.retain(|_, texts| {
+        texts.retain(|_, entry| keep(entry));
+        !texts.is_empty()
+    });
 }
 
 pub struct RenderedTextResult<'lt> {
@@ -47,8 +328,8 @@ pub struct RenderedTextResult<'lt> {
     h: u32,
 }
 
-impl RenderedTextResult<'_> {
-    pub fn texture(&self) -> &Texture {
+impl<'lt> RenderedTextResult<'lt> {
+    pub fn texture(&self) -> &Texture<'lt> {
         &self.texture
     }
 
@@ -0,0 +1,457 @@
+use crate::args::option;
+use crate::i18n::{Language, Text};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+// fichier de configuration utilisé si --config n'est pas précisé
+const DEFAULT_PATH: &str = "rustdancer.cfg";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Generator {
+    Rooms,
+    Caves,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+// FREE : les commandes sont reçues à chaque tick
+// STRICT : les commandes ne sont reçues que sur les temps
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Rhythm {
+    Free,
+    Strict,
+}
+
+// paramètres modifiables depuis l'écran d'accueil, pris en compte à la partie suivante
+#[derive(Clone, PartialEq)]
+pub struct Settings {
+    pub bpm: u32,
+    pub map_size: u32,
+    pub generator: Generator,
+    pub difficulty: Difficulty,
+    pub fog: bool,
+    pub rhythm: Rhythm,
+    pub port: u16,
+    pub language: Language,
+    pub volume: u32,
+    pub fullscreen: bool,
+    // les lutins ne sont agrandis que d'un nombre entier de fois, pour rester nets
+    pub integer_scaling: bool,
+}
+
+// lignes de l'écran des paramètres, dans l'ordre d'affichage
+#[derive(Copy, Clone, PartialEq)]
+pub enum Setting {
+    Bpm,
+    MapSize,
+    Generator,
+    Difficulty,
+    Fog,
+    Rhythm,
+    Port,
+    Language,
+    Volume,
+    Fullscreen,
+    IntegerScaling,
+}
+
+pub const SETTINGS: [Setting; 11] = [
+    Setting::Bpm,
+    Setting::MapSize,
+    Setting::Generator,
+    Setting::Difficulty,
+    Setting::Fog,
+    Setting::Rhythm,
+    Setting::Port,
+    Setting::Language,
+    Setting::Volume,
+    Setting::Fullscreen,
+    Setting::IntegerScaling,
+];
+
+const BPM_RANGE: (u32, u32) = (60, 240);
+// les cartes ont une taille impaire pour que la sortie soit au centre
+const MAP_SIZE_RANGE: (u32, u32) = (25, 75);
+const VOLUME_STEP: u32 = 10;
+// clés des paramètres qui changent la partie elle-même, et non son affichage
+const GAME_KEYS: [&str; 6] = [
+    "bpm",
+    "map_size",
+    "generator",
+    "difficulty",
+    "fog",
+    "rhythm",
+];
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            bpm: 120,
+            map_size: 37,
+            generator: Generator::Rooms,
+            difficulty: Difficulty::Normal,
+            fog: false,
+            rhythm: Rhythm::Free,
+            port: 54321,
+            language: Language::French,
+            volume: 100,
+            fullscreen: false,
+            integer_scaling: true,
+        }
+    }
+
+    // option reconnue : --config <fichier>
+    pub fn path(args: &[String]) -> PathBuf {
+        option(args, "--config").map_or(PathBuf::from(DEFAULT_PATH), PathBuf::from)
+    }
+
+    // un fichier absent donne les paramètres par défaut ; les lignes invalides sont ignorées
+    pub fn load(path: &Path) -> Self {
+        let mut settings = Self::new();
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                info!(
+                    "paramètres par défaut, {} n'a pas pu être lu : {}",
+                    path.display(),
+                    err
+                );
+                return settings;
+            }
+        };
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line
+                .split_once('=')
+                .map(|(key, value)| settings.set(key.trim(), value.trim()));
+            if parsed != Some(true) {
+                warn!("ligne « {} » de {} ignorée", line, path.display());
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        let mut content = "# paramètres de Crypt Of The RustDancer\n".to_string();
+        for (key, value) in self.entries() {
+            writeln!(content, "{} = {}", key, value).unwrap_or(());
+        }
+        std::fs::write(path, content)
+    }
+
+    // paramètres qui déterminent le déroulement d'une partie
+    pub fn game_entries(&self) -> Vec<(&'static str, String)> {
+        self.entries()
+            .into_iter()
+            .filter(|(key, _)| GAME_KEYS.contains(key))
+            .collect()
+    }
+
+    fn entries(&self) -> [(&'static str, String); 11] {
+        [
+            ("bpm", self.bpm.to_string()),
+            ("map_size", self.map_size.to_string()),
+            (
+                "generator",
+                match self.generator {
+                    Generator::Rooms => "rooms",
+                    Generator::Caves => "caves",
+                }
+                .to_string(),
+            ),
+            (
+                "difficulty",
+                match self.difficulty {
+                    Difficulty::Easy => "easy",
+                    Difficulty::Normal => "normal",
+                    Difficulty::Hard => "hard",
+                }
+                .to_string(),
+            ),
+            ("fog", on_off(self.fog).to_string()),
+            (
+                "rhythm",
+                match self.rhythm {
+                    Rhythm::Free => "free",
+                    Rhythm::Strict => "strict",
+                }
+                .to_string(),
+            ),
+            ("port", self.port.to_string()),
+            ("language", self.language.code().to_ascii_lowercase()),
+            ("volume", self.volume.to_string()),
+            ("fullscreen", on_off(self.fullscreen).to_string()),
+            ("integer_scaling", on_off(self.integer_scaling).to_string()),
+        ]
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        match (key, value) {
+            ("bpm", value) => match u32::from_str(value) {
+                Ok(bpm) if BPM_RANGE.0 <= bpm && bpm <= BPM_RANGE.1 => self.bpm = bpm,
+                _ => return false,
+            },
+            ("map_size", value) => match u32::from_str(value) {
+                Ok(size)
+                    if MAP_SIZE_RANGE.0 <= size && size <= MAP_SIZE_RANGE.1 && size % 2 == 1 =>
+                {
+                    self.map_size = size
+                }
+                _ => return false,
+            },
+            ("generator", "rooms") => self.generator = Generator::Rooms,
+            ("generator", "caves") => self.generator = Generator::Caves,
+            ("difficulty", "easy") => self.difficulty = Difficulty::Easy,
+            ("difficulty", "normal") => self.difficulty = Difficulty::Normal,
+            ("difficulty", "hard") => self.difficulty = Difficulty::Hard,
+            ("fog", "on") => self.fog = true,
+            ("fog", "off") => self.fog = false,
+            ("rhythm", "free") => self.rhythm = Rhythm::Free,
+            ("rhythm", "strict") => self.rhythm = Rhythm::Strict,
+            ("port", value) => match u16::from_str(value) {
+                Ok(port) if port > 0 => self.port = port,
+                _ => return false,
+            },
+            ("language", value) => match Language::parse(value) {
+                Some(language) => self.language = language,
+                None => return false,
+            },
+            ("volume", value) => match u32::from_str(value) {
+                Ok(volume) if volume <= 100 => self.volume = volume,
+                _ => return false,
+            },
+            ("fullscreen", "on") => self.fullscreen = true,
+            ("fullscreen", "off") => self.fullscreen = false,
+            ("integer_scaling", "on") => self.integer_scaling = true,
+            ("integer_scaling", "off") => self.integer_scaling = false,
+            _ => return false,
+        }
+        true
+    }
+
+    // durée d'un tick : il y a quatre ticks par temps
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_millis(60_000 / (self.bpm as u64 * 4))
+    }
+
+    // passe à la valeur suivante (ou précédente) d'un paramètre, en boucle
+    pub fn change(&mut self, setting: Setting, forward: bool) {
+        match setting {
+            Setting::Bpm => self.bpm = step(self.bpm, 10, BPM_RANGE, forward),
+            Setting::MapSize => self.map_size = step(self.map_size, 2, MAP_SIZE_RANGE, forward),
+            Setting::Generator => {
+                self.generator = match self.generator {
+                    Generator::Rooms => Generator::Caves,
+                    Generator::Caves => Generator::Rooms,
+                }
+            }
+            Setting::Difficulty => {
+                self.difficulty = match (self.difficulty, forward) {
+                    (Difficulty::Easy, true) | (Difficulty::Hard, false) => Difficulty::Normal,
+                    (Difficulty::Normal, true) | (Difficulty::Easy, false) => Difficulty::Hard,
+                    (Difficulty::Hard, true) | (Difficulty::Normal, false) => Difficulty::Easy,
+                }
+            }
+            Setting::Fog => self.fog = !self.fog,
+            Setting::Rhythm => {
+                self.rhythm = match self.rhythm {
+                    Rhythm::Free => Rhythm::Strict,
+                    Rhythm::Strict => Rhythm::Free,
+                }
+            }
+            Setting::Port => {
+                self.port = step(self.port as u32, 1, (1, u16::MAX as u32), forward) as u16
+            }
+            Setting::Language => {
+                self.language = match self.language {
+                    Language::French => Language::English,
+                    Language::English => Language::French,
+                }
+            }
+            Setting::Volume => self.volume = step(self.volume, VOLUME_STEP, (0, 100), forward),
+            Setting::Fullscreen => self.fullscreen = !self.fullscreen,
+            Setting::IntegerScaling => self.integer_scaling = !self.integer_scaling,
+        }
+    }
+
+    // le port peut aussi être saisi au clavier, chiffre par chiffre
+    pub fn type_port_digit(&mut self, digit: u32) {
+        let port = self.port as u32 * 10 + digit;
+        if port <= u16::MAX as u32 {
+            self.port = port as u16;
+        }
+    }
+
+    pub fn erase_port_digit(&mut self) {
+        self.port /= 10;
+        if self.port == 0 {
+            self.port = 1;
+        }
+    }
+
+    pub fn label(setting: Setting) -> Text {
+        match setting {
+            Setting::Bpm => Text::Bpm,
+            Setting::MapSize => Text::MapSize,
+            Setting::Generator => Text::Generator,
+            Setting::Difficulty => Text::Difficulty,
+            Setting::Fog => Text::Fog,
+            Setting::Rhythm => Text::Rhythm,
+            Setting::Port => Text::Port,
+            Setting::Language => Text::Language,
+            Setting::Volume => Text::Volume,
+            Setting::Fullscreen => Text::Fullscreen,
+            Setting::IntegerScaling => Text::IntegerScaling,
+        }
+    }
+
+    pub fn value(&self, setting: Setting, language: Language) -> String {
+        let text = |enabled: bool| language.text(if enabled { Text::On } else { Text::Off });
+        match setting {
+            Setting::Bpm => self.bpm.to_string(),
+            Setting::MapSize => format!("{} x {}", self.map_size, self.map_size),
+            Setting::Generator => language
+                .text(match self.generator {
+                    Generator::Rooms => Text::Rooms,
+                    Generator::Caves => Text::Caves,
+                })
+                .to_string(),
+            Setting::Difficulty => language
+                .text(match self.difficulty {
+                    Difficulty::Easy => Text::Easy,
+                    Difficulty::Normal => Text::Normal,
+                    Difficulty::Hard => Text::Hard,
+                })
+                .to_string(),
+            Setting::Fog => text(self.fog).to_string(),
+            Setting::Rhythm => language
+                .text(match self.rhythm {
+                    Rhythm::Free => Text::Free,
+                    Rhythm::Strict => Text::Strict,
+                })
+                .to_string(),
+            Setting::Port => self.port.to_string(),
+            // chaque langue est écrite dans cette langue, pour qu'on puisse toujours la retrouver
+            Setting::Language => match self.language {
+                Language::French => "Français".to_string(),
+                Language::English => "English".to_string(),
+            },
+            Setting::Volume => format!("{} %", self.volume),
+            Setting::Fullscreen => text(self.fullscreen).to_string(),
+            Setting::IntegerScaling => text(self.integer_scaling).to_string(),
+        }
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn step(value: u32, increment: u32, range: (u32, u32), forward: bool) -> u32 {
+    if forward {
+        if value + increment > range.1 {
+            range.0
+        } else {
+            value + increment
+        }
+    } else if value < range.0 + increment {
+        range.1
+    } else {
+        value - increment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // fichier propre à chaque test, dans le dossier temporaire
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rustdancer-{}-{}.cfg", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn save_then_load_gives_the_same_settings() {
+        let settings = Settings {
+            bpm: 150,
+            map_size: 51,
+            generator: Generator::Caves,
+            difficulty: Difficulty::Hard,
+            fog: true,
+            rhythm: Rhythm::Strict,
+            port: 4242,
+            language: Language::English,
+            volume: 30,
+            fullscreen: true,
+            integer_scaling: false,
+        };
+        let path = temp_file("round-trip", "");
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded == settings);
+    }
+
+    #[test]
+    fn malformed_lines_are_ignored() {
+        let path = temp_file(
+            "malformed",
+            "# commentaire\n\
+             \n\
+             bpm = 999\n\
+             map_size = 26\n\
+             map_size = 101\n\
+             fog = peut-être\n\
+             port = 0\n\
+             volume = 101\n\
+             couleur = rouge\n\
+             sans égal\n\
+             language = de\n\
+             \x20 volume=40 \n\
+             difficulty = easy\n",
+        );
+        let loaded = Settings::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let expected = Settings {
+            volume: 40,
+            difficulty: Difficulty::Easy,
+            ..Settings::new()
+        };
+        assert!(loaded == expected);
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let path = std::env::temp_dir().join("rustdancer-absent.cfg");
+        assert!(Settings::load(&path) == Settings::new());
+    }
+
+    #[test]
+    fn game_entries_only_concern_the_game() {
+        let keys: Vec<&str> = Settings::new()
+            .game_entries()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, GAME_KEYS);
+    }
+}
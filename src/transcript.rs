@@ -0,0 +1,183 @@
+use crate::args::option;
+use crate::log;
+use crate::settings::Settings;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// enregistrement de tout ce qui passe sur une connexion, pour comprendre ce qu'un bot
+// a vraiment envoyé et pouvoir le rejouer avec replay.py
+//
+// chaque ligne du fichier a la forme « <date> <tick> <sens> <données> », où le sens vaut
+// « < » pour un message reçu, « > » pour des données envoyées et « # » pour une remarque ;
+// les octets qui ne sont pas de l'UTF-8 valide ou pas affichables sont écrits \xNN
+//
+// la première ligne, de sens « = », donne la graine et les paramètres de la partie sous la
+// forme « clé=valeur », dont tick_ms, la durée d'un tick en millisecondes
+//
+// un message reçu est daté du tick où le jeu l'a traité, plus tardif que sa réception pendant
+// une action ou en attendant un temps ; un message refusé par les limites n'est jamais traité
+// et garde le tick de sa réception
+#[derive(Clone)]
+pub struct Transcript {
+    shared: Arc<Mutex<Shared>>,
+    tick: Arc<AtomicU32>,
+}
+
+// partagé entre le thread réseau, qui enregistre ce qui est reçu et envoyé, et le thread
+// du jeu, qui signale chaque message traité
+struct Shared {
+    file: BufWriter<File>,
+    // messages reçus pas encore traités, avec le tick de leur réception
+    pending: VecDeque<(u32, char, String)>,
+}
+
+// option reconnue : --transcript <dossier>
+pub fn directory(args: &[String]) -> Option<PathBuf> {
+    option(args, "--transcript").map(PathBuf::from)
+}
+
+impl Transcript {
+    // le tick est mis à jour par le thread du jeu et lu au moment de chaque enregistrement
+    pub fn create(
+        directory: &PathBuf,
+        connection: u32,
+        tick: Arc<AtomicU32>,
+        seed: u64,
+        settings: &Settings,
+    ) -> Result<Self, std::io::Error> {
+        std::fs::create_dir_all(directory)?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = directory.join(format!("connexion-{}-{}.txt", started, connection));
+
+        let mut header = format!("seed={}", seed);
+        for (key, value) in settings.game_entries() {
+            header.push_str(&format!(" {}={}", key, value));
+        }
+        header.push_str(&format!(
+            " tick_ms={}",
+            settings.tick_duration().as_millis()
+        ));
+
+        let mut shared = Shared {
+            file: BufWriter::new(File::create(path)?),
+            pending: VecDeque::new(),
+        };
+        shared.write(tick.load(Ordering::Relaxed), '=', &header)?;
+        Ok(Self {
+            shared: Arc::new(Mutex::new(shared)),
+            tick,
+        })
+    }
+
+    // le message n'est écrit que lorsque le jeu le traite (voir processed)
+    pub fn received(&mut self, data: &[u8]) {
+        self.receive('<', escape(data));
+    }
+
+    // un message trop long n'est pas enregistré, mais le jeu traite quand même l'erreur
+    pub fn received_oversized(&mut self) {
+        self.receive('#', "message trop long ignoré".to_string());
+    }
+
+    // message reçu que le jeu ne traitera pas
+    pub fn refused(&mut self, data: &[u8]) -> Result<(), std::io::Error> {
+        self.record('<', &escape(data))
+    }
+
+    // appelé par le thread du jeu au moment où il traite le plus ancien message reçu
+    pub fn processed(&mut self, tick: u32) -> Result<(), std::io::Error> {
+        let mut shared = self.shared.lock().unwrap_or_else(|err| err.into_inner());
+        match shared.pending.pop_front() {
+            Some((_, direction, data)) => shared.write(tick, direction, &data),
+            None => Ok(()),
+        }
+    }
+
+    // en trames texte, chaque ligne envoyée est enregistrée séparément
+    pub fn sent(&mut self, data: &[u8], lines: bool) -> Result<(), std::io::Error> {
+        if lines {
+            for line in data.strip_suffix(b"\n").unwrap_or(data).split(|c| *c == 10) {
+                self.record('>', &escape(line))?;
+            }
+            Ok(())
+        } else {
+            self.record('>', &escape(data))
+        }
+    }
+
+    pub fn note(&mut self, text: &str) -> Result<(), std::io::Error> {
+        self.record('#', text)
+    }
+
+    fn receive(&mut self, direction: char, data: String) {
+        let tick = self.tick.load(Ordering::Relaxed);
+        let mut shared = self.shared.lock().unwrap_or_else(|err| err.into_inner());
+        shared.pending.push_back((tick, direction, data));
+    }
+
+    fn record(&mut self, direction: char, data: &str) -> Result<(), std::io::Error> {
+        let tick = self.tick.load(Ordering::Relaxed);
+        let mut shared = self.shared.lock().unwrap_or_else(|err| err.into_inner());
+        shared.write(tick, direction, data)
+    }
+}
+
+impl Shared {
+    fn write(&mut self, tick: u32, direction: char, data: &str) -> Result<(), std::io::Error> {
+        writeln!(
+            self.file,
+            "{} {} {} {}",
+            log::timestamp(),
+            tick,
+            direction,
+            data
+        )?;
+        // le fichier doit être complet même si le serveur s'arrête brutalement
+        self.file.flush()
+    }
+}
+
+// les messages que le jeu n'a pas eu le temps de traiter avant la fin de la connexion sont
+// écrits à la fin, avec le tick de leur réception
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        if let Some((tick, _, _)) = pending.front() {
+            self.write(*tick, '#', "messages reçus mais pas traités par le jeu :")
+                .unwrap_or(());
+        }
+        for (tick, direction, data) in pending {
+            self.write(tick, direction, &data).unwrap_or(());
+        }
+    }
+}
+
+fn escape(data: &[u8]) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for chunk in data.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                c if c.is_control() => {
+                    let mut bytes = [0; 4];
+                    for byte in c.encode_utf8(&mut bytes).bytes() {
+                        escaped.push_str(&format!("\\x{:02x}", byte));
+                    }
+                }
+                c => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    escaped
+}
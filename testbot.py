@@ -1,7 +1,13 @@
 import socket
 
 with socket.create_connection(("127.0.0.1", 54321)) as conn:
+    lines = conn.makefile("r", encoding="utf-8", newline="\n")
+    print(lines.readline().rstrip("\n"))
+
     conn.send(b"MAP\n")
 
-    for i in range(37):
-        print(conn.recv(37))
+    _, width, height, rows = lines.readline().split()
+    print("carte de {} x {}".format(width, height))
+    for i in range(int(rows)):
+        print(lines.readline().rstrip("\n"))
+    assert lines.readline().rstrip("\n") == "ENDMAP"